@@ -0,0 +1,466 @@
+//! Assignment of tracking numbers to new scenarios.
+//!
+//! Newly staged scenarios without tracking number get a tag like `@tc:1005`,
+//! inserted on its own line directly above the scenario keyword.
+//! The rest of the file is left untouched.
+//!
+//! Numbers come from a [`NumberSource`]:
+//! the number after the highest one in the repository, a reserved range,
+//! or a local allocation file with numbers reserved for the developer.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    fs, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use git2::Repository;
+use serde::Deserialize;
+
+use crate::{
+    changed_tests,
+    scan::{scan, Revision},
+    ChangeKind, ExtractNumberError, Operation, Options, TestCase, Version,
+};
+
+/// Where new tracking numbers come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Allocation {
+    /// The numbers after the highest tracking number in the repository.
+    #[default]
+    Next,
+    /// The lowest unused numbers of a reserved range.
+    Range,
+    /// Numbers listed in a local allocation file, which are removed once used.
+    File,
+}
+
+/// An inclusive range of tracking numbers, written as `5000-5999`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct NumberRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A configured [`Allocation`], together with its settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberSource {
+    Next,
+    Range(NumberRange),
+    /// Path of the allocation file, relative to the root of the repository.
+    File(PathBuf),
+}
+
+/// A tracking number given to a scenario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    /// The scenario, with its position before the tag was inserted.
+    pub test: TestCase,
+    pub number: u32,
+}
+
+/// Possible errors while assigning numbers.
+#[derive(Debug)]
+pub enum AssignError {
+    /// The new scenarios or the used numbers could not be determined.
+    Detect(ExtractNumberError),
+    /// The source has fewer unused numbers than there are new scenarios.
+    Exhausted { needed: usize, available: usize },
+    /// The allocation file could not be read or written.
+    AllocationFile { path: PathBuf, source: io::Error },
+    /// A line of the allocation file is neither a number nor a range.
+    InvalidAllocationFile { path: PathBuf, line: String },
+    /// The file in the working directory differs from the staged version,
+    /// so the positions of the scenarios are unknown.
+    UnstagedChanges(PathBuf),
+    /// A feature file could not be written.
+    Write { path: PathBuf, source: io::Error },
+    /// The changed feature files could not be staged.
+    Stage {
+        path: Option<PathBuf>,
+        source: git2::Error,
+    },
+    /// There is no working directory with feature files to change.
+    BareRepository,
+}
+
+/// Staged scenarios without tracking number that did not exist before.
+///
+/// A scenario is new if the line with its keyword was added.
+pub fn new_untagged_scenarios(
+    repo: &Repository,
+    opts: &Options,
+) -> Result<Vec<TestCase>, ExtractNumberError> {
+    let changes = changed_tests(repo, opts)?;
+
+    Ok(changes
+        .tests
+        .into_iter()
+        .filter(|test| test.number.is_none() && test.kind == ChangeKind::TestCase)
+        .filter_map(|test| {
+            let case = test.test?;
+            let is_new = test.lines.iter().any(|line| {
+                line.version == Version::New
+                    && line.path == case.path
+                    && line.line as usize == case.line
+            });
+            is_new.then_some(case)
+        })
+        .filter(|case| is_gherkin(&case.path, opts))
+        .collect())
+}
+
+/// Tag all new untagged scenarios with numbers from `source`.
+///
+/// The feature files are changed in the working directory and staged again.
+/// With `dry_run`, the numbers are only determined, but nothing is written.
+pub fn assign_numbers(
+    repo: &Repository,
+    opts: &Options,
+    source: &NumberSource,
+    dry_run: bool,
+) -> Result<Vec<Assignment>, AssignError> {
+    let scenarios = new_untagged_scenarios(repo, opts).map_err(AssignError::Detect)?;
+    if scenarios.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let workdir = repo
+        .workdir()
+        .ok_or(AssignError::BareRepository)?
+        .to_owned();
+
+    // Check all files before anything is changed
+    let mut files: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for scenario in &scenarios {
+        if !files.contains_key(&scenario.path) {
+            let staged = staged_text(repo, &scenario.path)?;
+            let current = fs::read(workdir.join(&scenario.path)).unwrap_or_default();
+            if current != staged.as_bytes() {
+                return Err(AssignError::UnstagedChanges(scenario.path.clone()));
+            }
+            files.insert(scenario.path.clone(), staged);
+        }
+    }
+
+    let used: BTreeSet<u32> = scan(repo, &Revision::Index, opts)
+        .map_err(AssignError::Detect)?
+        .tests
+        .iter()
+        .filter_map(|test| test.number)
+        .collect();
+    let (numbers, pool) = allocate(source, &used, scenarios.len(), &workdir)?;
+
+    let assignments: Vec<_> = scenarios
+        .into_iter()
+        .zip(numbers)
+        .map(|(test, number)| Assignment { test, number })
+        .collect();
+    if dry_run {
+        return Ok(assignments);
+    }
+
+    let mut index = repo
+        .index()
+        .map_err(|source| AssignError::Stage { path: None, source })?;
+    for (path, text) in files {
+        let tags: Vec<_> = assignments
+            .iter()
+            .filter(|a| a.test.path == path)
+            .map(|a| (a.test.line, format!("{}{}", opts.test_prefix, a.number)))
+            .collect();
+
+        fs::write(workdir.join(&path), insert_tags(&text, &tags)).map_err(|source| {
+            AssignError::Write {
+                path: path.clone(),
+                source,
+            }
+        })?;
+        index.add_path(&path).map_err(|source| AssignError::Stage {
+            path: Some(path),
+            source,
+        })?;
+    }
+    index
+        .write()
+        .map_err(|source| AssignError::Stage { path: None, source })?;
+
+    // Only now the numbers are used, so they are not lost if tagging fails
+    if let Some(pool) = pool {
+        pool.write()?;
+    }
+
+    Ok(assignments)
+}
+
+/// Insert each tag on its own line above the given line, 1 based, with the same indentation.
+///
+/// Everything else, including the line endings, stays as it is.
+pub fn insert_tags(text: &str, tags: &[(usize, String)]) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (idx, line) in text.split_inclusive('\n').enumerate() {
+        for (_, tag) in tags.iter().filter(|(number, _)| *number == idx + 1) {
+            let indentation = &line[..line.len() - line.trim_start().len()];
+            let line_break = if line.ends_with("\r\n") { "\r\n" } else { "\n" };
+            result.push_str(&format!("{indentation}@{tag}{line_break}"));
+        }
+        result.push_str(line);
+    }
+    result
+}
+
+/// What is left of an allocation file after numbers were taken from it.
+struct RemainingPool {
+    path: PathBuf,
+    /// Contents of the file before the numbers were taken.
+    text: String,
+    /// The rest of each range of the file, `None` if it is used up.
+    ranges: Vec<Option<NumberRange>>,
+}
+
+/// The next `count` unused numbers of the source.
+///
+/// For an allocation file, the numbers left in it are returned as well,
+/// the file itself is not changed.
+fn allocate(
+    source: &NumberSource,
+    used: &BTreeSet<u32>,
+    count: usize,
+    workdir: &Path,
+) -> Result<(Vec<u32>, Option<RemainingPool>), AssignError> {
+    let exhausted = |available| AssignError::Exhausted {
+        needed: count,
+        available,
+    };
+
+    match source {
+        NumberSource::Next => {
+            let first = used.last().map_or(1, |last| last.saturating_add(1));
+            let numbers: Vec<_> = (first..=u32::MAX).take(count).collect();
+            if numbers.len() < count {
+                return Err(exhausted(numbers.len()));
+            }
+            Ok((numbers, None))
+        }
+        NumberSource::Range(range) => {
+            let numbers: Vec<_> = range
+                .numbers()
+                .filter(|n| !used.contains(n))
+                .take(count)
+                .collect();
+            if numbers.len() < count {
+                return Err(exhausted(numbers.len()));
+            }
+            Ok((numbers, None))
+        }
+        NumberSource::File(path) => {
+            let path = workdir.join(path);
+            let text = fs::read_to_string(&path).map_err(|source| AssignError::AllocationFile {
+                path: path.clone(),
+                source,
+            })?;
+
+            let mut numbers = Vec::new();
+            let mut ranges = Vec::new();
+            for range in parse_allocation_file(&path, &text)? {
+                let mut candidates = range.numbers();
+                while numbers.len() < count {
+                    let Some(number) = candidates.next() else {
+                        break;
+                    };
+                    if !used.contains(&number) {
+                        numbers.push(number);
+                    }
+                }
+                // Whatever is left of the range stays reserved
+                ranges.push(
+                    candidates
+                        .next()
+                        .map(|next| NumberRange::new(next, range.end)),
+                );
+            }
+            if numbers.len() < count {
+                return Err(exhausted(numbers.len()));
+            }
+
+            Ok((numbers, Some(RemainingPool { path, text, ranges })))
+        }
+    }
+}
+
+/// The ranges of an allocation file.
+///
+/// Each line is a number or a range like `5000-5999`, empty lines and `#` comments are skipped.
+fn parse_allocation_file(path: &Path, text: &str) -> Result<Vec<NumberRange>, AssignError> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !is_allocation_comment(line))
+        .map(|line| {
+            line.parse()
+                .map_err(|_| AssignError::InvalidAllocationFile {
+                    path: path.to_owned(),
+                    line: line.to_owned(),
+                })
+        })
+        .collect()
+}
+
+/// Whether a trimmed line of an allocation file is empty or a `#` comment.
+fn is_allocation_comment(line: &str) -> bool {
+    line.is_empty() || line.starts_with('#')
+}
+
+impl RemainingPool {
+    /// Replace each range of the file with its rest, keeping comments and empty lines.
+    fn write(self) -> Result<(), AssignError> {
+        let mut ranges = self.ranges.into_iter();
+        let mut text = String::with_capacity(self.text.len());
+        for line in self.text.split_inclusive('\n') {
+            if is_allocation_comment(line.trim()) {
+                text.push_str(line);
+            } else if let Some(range) = ranges.next().flatten() {
+                let line_break = if line.ends_with("\r\n") { "\r\n" } else { "\n" };
+                text.push_str(&format!("{range}{line_break}"));
+            }
+        }
+
+        fs::write(&self.path, text).map_err(|source| AssignError::AllocationFile {
+            path: self.path,
+            source,
+        })
+    }
+}
+
+/// The staged contents of a file.
+fn staged_text(repo: &Repository, path: &Path) -> Result<String, AssignError> {
+    let git_error = |operation| {
+        move |source| {
+            AssignError::Detect(ExtractNumberError::Git {
+                operation,
+                path: Some(path.to_owned()),
+                source,
+            })
+        }
+    };
+
+    let index = repo.index().map_err(git_error(Operation::ReadIndex))?;
+    let entry = index.get_path(path, 0).ok_or_else(|| {
+        git_error(Operation::ReadIndex)(git2::Error::from_str("the file is not staged"))
+    })?;
+    let blob = repo
+        .find_blob(entry.id)
+        .map_err(git_error(Operation::ReadBlob))?;
+    Ok(String::from_utf8_lossy(blob.content()).into_owned())
+}
+
+/// Tags can only be inserted into Gherkin files.
+fn is_gherkin(path: &Path, opts: &Options) -> bool {
+    opts.formats
+        .iter()
+        .find(|f| f.handles(path))
+        .is_some_and(|f| f.as_gherkin().is_some())
+}
+
+impl NumberRange {
+    pub fn new(start: u32, end: u32) -> Self {
+        Self { start, end }
+    }
+
+    fn numbers(&self) -> RangeInclusive<u32> {
+        self.start..=self.end
+    }
+}
+
+impl FromStr for NumberRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{s}' is not a range like 5000-5999");
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let start = start.trim().parse().map_err(|_| invalid())?;
+        let end = end.trim().parse().map_err(|_| invalid())?;
+        if start > end {
+            return Err(invalid());
+        }
+        Ok(Self { start, end })
+    }
+}
+
+impl TryFrom<String> for NumberRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for NumberRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl Display for AssignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssignError::Detect(err) => write!(f, "{err}"),
+            AssignError::Exhausted { needed, available } => write!(
+                f,
+                "Not enough tracking numbers available, {needed} needed but only {available} left"
+            ),
+            AssignError::AllocationFile { path, source } => write!(
+                f,
+                "Failed to access the allocation file {}: {source}",
+                path.display()
+            ),
+            AssignError::InvalidAllocationFile { path, line } => write!(
+                f,
+                "Invalid line '{line}' in the allocation file {}, expected a number or a range like 5000-5999",
+                path.display()
+            ),
+            AssignError::UnstagedChanges(path) => write!(
+                f,
+                "{} has unstaged changes, stage or discard them first",
+                path.display()
+            ),
+            AssignError::Write { path, source } => {
+                write!(f, "Failed to write {}: {source}", path.display())
+            }
+            AssignError::Stage {
+                path: Some(path),
+                source,
+            } => write!(f, "Failed to stage {}: {source}", path.display()),
+            AssignError::Stage { path: None, source } => {
+                write!(f, "Failed to stage the changes: {source}")
+            }
+            AssignError::BareRepository => {
+                write!(f, "Tracking numbers can't be assigned in a bare repository")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssignError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssignError::Detect(err) => Some(err),
+            AssignError::AllocationFile { source, .. } | AssignError::Write { source, .. } => {
+                Some(source)
+            }
+            AssignError::Stage { source, .. } => Some(source),
+            AssignError::Exhausted { .. }
+            | AssignError::InvalidAllocationFile { .. }
+            | AssignError::UnstagedChanges(_)
+            | AssignError::BareRepository => None,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Comparison of the trailers in the history with the actual changes.
+//!
+//! Commit messages can be edited freely, e.g. when the hook was skipped or a commit was amended,
+//! so the trailer of a commit does not have to match what the commit changed.
+//! The audit detects the numbers of every commit again and reports the differences.
+
+use git2::{Oid, Repository};
+
+use crate::{
+    history::{commits, HistoryError, NumberCache},
+    parse_issue_references, Options,
+};
+
+/// The differences between the trailer of a commit and its changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitAudit {
+    pub id: Oid,
+    /// First line of the commit message.
+    pub summary: String,
+    /// Numbers of changed test cases that are not in the trailer.
+    pub missing: Vec<u32>,
+    /// Numbers in the trailer whose test cases did not change.
+    pub spurious: Vec<u32>,
+}
+
+/// Compare the trailer of every commit in `range` with the numbers detected from its changes.
+///
+/// Only the commits with differences are returned, newest first.
+/// The numbers of each commit are taken from the `cache` if possible.
+pub fn audit(
+    repo: &Repository,
+    range: &str,
+    trailer: &str,
+    opts: &Options,
+    cache: &mut NumberCache,
+) -> Result<Vec<CommitAudit>, HistoryError> {
+    let mut audits = Vec::new();
+    for commit in commits(repo, range)? {
+        let changed = cache.numbers(repo, &commit, opts)?;
+        let referenced =
+            parse_issue_references(&String::from_utf8_lossy(commit.message_bytes()), trailer);
+
+        let audit = CommitAudit {
+            id: commit.id(),
+            summary: commit.summary().unwrap_or_default().to_owned(),
+            missing: difference(&changed, &referenced),
+            spurious: difference(&referenced, &changed),
+        };
+        if !audit.is_consistent() {
+            audits.push(audit);
+        }
+    }
+    Ok(audits)
+}
+
+impl CommitAudit {
+    /// Whether the trailer matches the changes.
+    pub fn is_consistent(&self) -> bool {
+        self.missing.is_empty() && self.spurious.is_empty()
+    }
+}
+
+/// Numbers in `a` that are not in `b`.
+fn difference(a: &[u32], b: &[u32]) -> Vec<u32> {
+    a.iter().copied().filter(|n| !b.contains(n)).collect()
+}
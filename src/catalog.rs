@@ -0,0 +1,254 @@
+//! The tracking numbers known to the test management tool.
+//!
+//! A typo like `@tc:10002` instead of `@tc:1002` is a valid tracking number,
+//! so neither the hook nor the lint would notice it. With a catalog of the known numbers,
+//! changed test cases referencing an unknown or retired number are reported.
+//!
+//! The catalog is a CSV file with an `id` column and an optional `status` column:
+//!
+//! ```csv
+//! id,status
+//! 1001,active
+//! 1002,retired
+//! ```
+//!
+//! or a JSON file with an array of numbers or of objects like `{"id": 1002, "status": "retired"}`.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{ChangedTests, TestCase};
+
+/// Status of test cases that must not be referenced any more.
+pub const RETIRED: &str = "retired";
+
+/// The known tracking numbers with their status.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    /// Status by number, empty if the catalog has none.
+    numbers: BTreeMap<u32, String>,
+}
+
+/// Why a referenced number is rejected by the catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Problem {
+    /// The number is not in the catalog, e.g. because of a typo.
+    Unknown,
+    /// The number has the status `retired`.
+    Retired,
+}
+
+/// A changed test case referencing a number rejected by the catalog.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Violation {
+    pub number: u32,
+    /// The test case, `None` if it is only known by its number, e.g. via [`crate::Options::fixtures`].
+    pub test: Option<TestCase>,
+    pub problem: Problem,
+}
+
+/// Possible errors while reading the catalog.
+#[derive(Debug)]
+pub enum CatalogError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// A line of a CSV catalog has no valid number.
+    InvalidLine {
+        path: PathBuf,
+        line: usize,
+    },
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    Number(u32),
+    Entry {
+        id: u32,
+        #[serde(default)]
+        status: String,
+    },
+}
+
+impl Catalog {
+    /// Read a catalog, as JSON if the file name ends with `.json` and as CSV otherwise.
+    pub fn load(path: &Path) -> Result<Self, CatalogError> {
+        let text = fs::read_to_string(path).map_err(|source| CatalogError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            Self::from_json(&text).map_err(|source| CatalogError::Json {
+                path: path.to_owned(),
+                source,
+            })
+        } else {
+            Self::from_csv(&text).map_err(|line| CatalogError::InvalidLine {
+                path: path.to_owned(),
+                line,
+            })
+        }
+    }
+
+    /// Parse a JSON array of numbers or of objects with `id` and `status`.
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        let entries: Vec<JsonEntry> = serde_json::from_str(text)?;
+        let numbers = entries
+            .into_iter()
+            .map(|entry| match entry {
+                JsonEntry::Number(number) => (number, String::new()),
+                JsonEntry::Entry { id, status } => (id, status),
+            })
+            .collect();
+        Ok(Self { numbers })
+    }
+
+    /// Parse CSV with the number in the first column and the status in the second.
+    ///
+    /// If the first line is a header, the columns named `id` and `status` are used instead.
+    /// Numbers may start with `#`, empty lines are skipped.
+    /// Returns the 1 based line number of an invalid line as error.
+    pub fn from_csv(text: &str) -> Result<Self, usize> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .peekable();
+
+        let (mut id_column, mut status_column) = (0, Some(1));
+        if let Some((_, header)) = lines.peek() {
+            let header = fields(header);
+            if parse_number(header.first().copied().unwrap_or_default()).is_none() {
+                let column = |name: &str| {
+                    header
+                        .iter()
+                        .position(|field| field.eq_ignore_ascii_case(name))
+                };
+                id_column = column("id").unwrap_or(0);
+                status_column = column("status");
+                lines.next();
+            }
+        }
+
+        let mut numbers = BTreeMap::new();
+        for (index, line) in lines {
+            let fields = fields(line);
+            let number = fields
+                .get(id_column)
+                .and_then(|field| parse_number(field))
+                .ok_or(index + 1)?;
+            let status = status_column
+                .and_then(|column| fields.get(column))
+                .copied()
+                .unwrap_or_default();
+            numbers.insert(number, status.to_owned());
+        }
+        Ok(Self { numbers })
+    }
+
+    /// Whether the number may be referenced, `None` if it is fine.
+    pub fn check(&self, number: u32) -> Option<Problem> {
+        match self.numbers.get(&number) {
+            None => Some(Problem::Unknown),
+            Some(status) if status.eq_ignore_ascii_case(RETIRED) => Some(Problem::Retired),
+            Some(_) => None,
+        }
+    }
+
+    /// The changed test cases whose numbers are unknown or retired, sorted by number.
+    pub fn violations(&self, changes: &ChangedTests) -> Vec<Violation> {
+        let mut violations: Vec<_> = changes
+            .tests
+            .iter()
+            .filter_map(|changed| {
+                let number = changed.number?;
+                Some(Violation {
+                    number,
+                    test: changed.test.clone(),
+                    problem: self.check(number)?,
+                })
+            })
+            .collect();
+        // A test case is listed once per reason it changed
+        violations.sort();
+        violations.dedup();
+        violations
+    }
+}
+
+/// The trimmed fields of a CSV line, without surrounding quotes.
+fn fields(line: &str) -> Vec<&str> {
+    line.split(',')
+        .map(|field| field.trim().trim_matches('"').trim())
+        .collect()
+}
+
+fn parse_number(field: &str) -> Option<u32> {
+    field.strip_prefix('#').unwrap_or(field).parse().ok()
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Unknown => f.write_str("is not in the catalog"),
+            Problem::Retired => f.write_str("is retired"),
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.test {
+            Some(test) => write!(
+                f,
+                "{test}: tracking number {} {}",
+                self.number, self.problem
+            ),
+            None => write!(f, "Tracking number {} {}", self.number, self.problem),
+        }
+    }
+}
+
+impl Display for CatalogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CatalogError::Io { path, source } => {
+                write!(f, "Failed to read the catalog {}: {source}", path.display())
+            }
+            CatalogError::InvalidLine { path, line } => write!(
+                f,
+                "Invalid tracking number in line {line} of the catalog {}",
+                path.display()
+            ),
+            CatalogError::Json { path, source } => {
+                write!(f, "Invalid catalog {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CatalogError::Io { source, .. } => Some(source),
+            CatalogError::InvalidLine { .. } => None,
+            CatalogError::Json { source, .. } => Some(source),
+        }
+    }
+}
@@ -0,0 +1,466 @@
+//! Settings that are shared by all developers of a repository.
+//!
+//! Settings are collected from multiple sources, later sources override earlier ones:
+//!
+//! 1. The `.show-changed-tests.toml` file in the root of the repository
+//! 2. The `showChangedTests.*` keys of `git config`
+//! 3. Command line arguments
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use git2::Repository;
+use serde::Deserialize;
+
+use crate::{
+    assign::{Allocation, NumberRange, NumberSource},
+    formats,
+    notes::{NoteFormat, DEFAULT_NOTES_REF},
+    Options, Policy,
+};
+
+/// Name of the configuration file in the repository root.
+pub const CONFIG_FILE: &str = ".show-changed-tests.toml";
+
+/// Section used for the keys in `git config`.
+pub const GIT_CONFIG_SECTION: &str = "showChangedTests";
+
+/// Partial configuration, as read from a single source.
+///
+/// Unset values fall back to the next source with lower precedence,
+/// and eventually to the built-in defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Prefix used in tags to link the test case to an item.
+    pub prefix: Option<String>,
+    /// Name of the trailer added to the commit message.
+    pub trailer: Option<String>,
+    /// Maximum width of a trailer line.
+    pub width: Option<usize>,
+    /// What to do with changed scenarios that have no tracking number.
+    pub untagged: Option<Policy>,
+    /// Glob patterns of the files that contain test cases.
+    pub include: Option<Vec<String>>,
+    /// Glob patterns of files to skip, even if they match `include`.
+    pub exclude: Option<Vec<String>>,
+    /// Glob patterns of files with step definitions.
+    pub step_definitions: Option<Vec<String>>,
+    /// Glob patterns of files used by test cases, mapped to their tracking numbers.
+    pub fixtures: Option<BTreeMap<String, Vec<u32>>>,
+    /// Whether to detect references to changed files in the steps of scenarios.
+    pub fixture_references: Option<bool>,
+    /// Language of Gherkin files without `# language:` header.
+    pub language: Option<String>,
+    /// Whether every problem fails with exit code 1, unless configured otherwise.
+    pub strict: Option<bool>,
+    /// Exit code if the hook fails, e.g. because the repository can not be read.
+    pub error_exit_code: Option<u8>,
+    /// Exit code if a file or step pattern could not be parsed.
+    pub parse_error_exit_code: Option<u8>,
+    /// Exit code if changed scenarios have no tracking number.
+    pub untagged_exit_code: Option<u8>,
+    /// Exit code if no changed tests were found.
+    pub no_changes_exit_code: Option<u8>,
+    /// CSV or JSON file with the known tracking numbers, relative to the repository root.
+    pub catalog: Option<PathBuf>,
+    /// What to do with changed test cases whose number is unknown or retired in the catalog.
+    pub unknown_numbers: Option<Policy>,
+    /// Exit code if changed test cases have an unknown or retired number.
+    pub unknown_numbers_exit_code: Option<u8>,
+    /// Where the numbers for new scenarios come from.
+    pub allocation: Option<Allocation>,
+    /// Numbers reserved for new scenarios, used by [`Allocation::Range`].
+    pub allocation_range: Option<NumberRange>,
+    /// File with numbers reserved for new scenarios, used by [`Allocation::File`].
+    pub allocation_file: Option<PathBuf>,
+    /// Ref of the git notes the changed tests are stored in.
+    pub notes_ref: Option<String>,
+    /// How the changed tests are written into a note.
+    pub notes_format: Option<NoteFormat>,
+    /// URL the notifier posts to for each changed test case, `{id}` is replaced by the number.
+    pub notify_url: Option<String>,
+    /// JSON template posted by the notifier, `{id}`, `{commit}` and `{range}` are replaced.
+    pub notify_payload: Option<String>,
+    /// HTTP headers sent by the notifier, `${VAR}` is replaced by the environment variable.
+    pub notify_headers: Option<BTreeMap<String, String>>,
+    /// How often the notifier retries a failed request.
+    pub notify_retries: Option<u32>,
+}
+
+/// The possible results of a run, each with its own exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The hook failed, e.g. because the repository could not be read.
+    Error,
+    /// A file or step pattern could not be parsed, so tests may be missing.
+    ParseError,
+    /// Changed scenarios without tracking number were found.
+    Untagged,
+    /// Changed test cases have a number that is unknown or retired in the catalog.
+    UnknownNumbers,
+    /// Neither tagged nor untagged changed tests were found.
+    NoChanges,
+}
+
+/// Possible errors while loading the configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Git(git2::Error),
+    InvalidValue {
+        key: String,
+        value: String,
+    },
+    /// A setting is required by another one, but not set.
+    MissingValue {
+        key: String,
+    },
+}
+
+impl Config {
+    /// Load the configuration file and the git config of the repository.
+    ///
+    /// The git config takes precedence over the configuration file.
+    pub fn load(repo: &Repository) -> Result<Self, ConfigError> {
+        let file = match repo.workdir() {
+            Some(workdir) => Self::from_file(&workdir.join(CONFIG_FILE))?,
+            None => Self::default(),
+        };
+        let git = Self::from_git_config(&repo.config()?)?;
+
+        Ok(file.merge(git))
+    }
+
+    /// Read a configuration file.
+    ///
+    /// A missing file is treated like an empty configuration.
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::from_toml(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Parse the contents of a configuration file.
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text)?;
+        if let Some(language) = &config.language {
+            check_language("language", language)?;
+        }
+        Ok(config)
+    }
+
+    /// Read the `showChangedTests.*` keys.
+    pub fn from_git_config(config: &git2::Config) -> Result<Self, ConfigError> {
+        let language = git_string(config, "language")?;
+        if let Some(language) = &language {
+            check_language(&git_key("language"), language)?;
+        }
+
+        Ok(Self {
+            prefix: git_string(config, "prefix")?,
+            trailer: git_string(config, "trailer")?,
+            width: git_parsed(config, "width")?,
+            untagged: git_enum(config, "untagged")?,
+            include: git_strings(config, "include")?,
+            exclude: git_strings(config, "exclude")?,
+            step_definitions: git_strings(config, "stepDefinitions")?,
+            // The mapping of fixtures is a table, which has no equivalent in git config
+            fixtures: None,
+            fixture_references: git_bool(config, "fixtureReferences")?,
+            language,
+            strict: git_bool(config, "strict")?,
+            error_exit_code: git_parsed(config, "errorExitCode")?,
+            parse_error_exit_code: git_parsed(config, "parseErrorExitCode")?,
+            untagged_exit_code: git_parsed(config, "untaggedExitCode")?,
+            no_changes_exit_code: git_parsed(config, "noChangesExitCode")?,
+            catalog: git_string(config, "catalog")?.map(PathBuf::from),
+            unknown_numbers: git_enum(config, "unknownNumbers")?,
+            unknown_numbers_exit_code: git_parsed(config, "unknownNumbersExitCode")?,
+            allocation: git_enum(config, "allocation")?,
+            allocation_range: git_parsed(config, "allocationRange")?,
+            allocation_file: git_string(config, "allocationFile")?.map(PathBuf::from),
+            notes_ref: git_string(config, "notesRef")?,
+            notes_format: git_enum(config, "notesFormat")?,
+            notify_url: git_string(config, "notifyUrl")?,
+            notify_payload: git_string(config, "notifyPayload")?,
+            // Header names can't be keys in git config
+            notify_headers: None,
+            notify_retries: git_parsed(config, "notifyRetries")?,
+        })
+    }
+
+    /// Combine two configurations, values set in `other` take precedence.
+    pub fn merge(self, other: Config) -> Config {
+        Config {
+            prefix: other.prefix.or(self.prefix),
+            trailer: other.trailer.or(self.trailer),
+            width: other.width.or(self.width),
+            untagged: other.untagged.or(self.untagged),
+            include: other.include.or(self.include),
+            exclude: other.exclude.or(self.exclude),
+            step_definitions: other.step_definitions.or(self.step_definitions),
+            fixtures: other.fixtures.or(self.fixtures),
+            fixture_references: other.fixture_references.or(self.fixture_references),
+            language: other.language.or(self.language),
+            strict: other.strict.or(self.strict),
+            error_exit_code: other.error_exit_code.or(self.error_exit_code),
+            parse_error_exit_code: other.parse_error_exit_code.or(self.parse_error_exit_code),
+            untagged_exit_code: other.untagged_exit_code.or(self.untagged_exit_code),
+            no_changes_exit_code: other.no_changes_exit_code.or(self.no_changes_exit_code),
+            catalog: other.catalog.or(self.catalog),
+            unknown_numbers: other.unknown_numbers.or(self.unknown_numbers),
+            unknown_numbers_exit_code: other
+                .unknown_numbers_exit_code
+                .or(self.unknown_numbers_exit_code),
+            allocation: other.allocation.or(self.allocation),
+            allocation_range: other.allocation_range.or(self.allocation_range),
+            allocation_file: other.allocation_file.or(self.allocation_file),
+            notes_ref: other.notes_ref.or(self.notes_ref),
+            notes_format: other.notes_format.or(self.notes_format),
+            notify_url: other.notify_url.or(self.notify_url),
+            notify_payload: other.notify_payload.or(self.notify_payload),
+            notify_headers: other.notify_headers.or(self.notify_headers),
+            notify_retries: other.notify_retries.or(self.notify_retries),
+        }
+    }
+
+    /// Options for detecting the changed tests.
+    pub fn options(&self) -> Options {
+        let defaults = Options::default();
+        Options {
+            test_prefix: self.prefix.clone().unwrap_or(defaults.test_prefix),
+            include: self.include.clone().unwrap_or(defaults.include),
+            exclude: self.exclude.clone().unwrap_or(defaults.exclude),
+            step_definitions: self
+                .step_definitions
+                .clone()
+                .unwrap_or(defaults.step_definitions),
+            fixtures: self.fixtures.clone().unwrap_or(defaults.fixtures),
+            fixture_references: self
+                .fixture_references
+                .unwrap_or(defaults.fixture_references),
+            formats: formats::default_formats(
+                self.language
+                    .as_deref()
+                    .unwrap_or(formats::DEFAULT_LANGUAGE),
+            ),
+        }
+    }
+
+    /// Where the numbers for new scenarios come from.
+    ///
+    /// Without explicit `allocation`, a configured allocation file or range is used,
+    /// otherwise the numbers after the highest one in the repository.
+    pub fn number_source(&self) -> Result<NumberSource, ConfigError> {
+        let allocation = self
+            .allocation
+            .unwrap_or(if self.allocation_file.is_some() {
+                Allocation::File
+            } else if self.allocation_range.is_some() {
+                Allocation::Range
+            } else {
+                Allocation::Next
+            });
+
+        let missing = |key: &str| ConfigError::MissingValue {
+            key: key.to_owned(),
+        };
+        match allocation {
+            Allocation::Next => Ok(NumberSource::Next),
+            Allocation::Range => self
+                .allocation_range
+                .map(NumberSource::Range)
+                .ok_or_else(|| missing("allocation-range")),
+            Allocation::File => self
+                .allocation_file
+                .clone()
+                .map(NumberSource::File)
+                .ok_or_else(|| missing("allocation-file")),
+        }
+    }
+
+    /// Exit code for an outcome.
+    ///
+    /// By default only [`Policy::Fail`] blocks the commit, so the hook never fails
+    /// because of its own problems. In strict mode every outcome except
+    /// [`Outcome::NoChanges`] exits with 1, unless it has its own exit code.
+    pub fn exit_code(&self, outcome: Outcome) -> u8 {
+        let configured = match outcome {
+            Outcome::Error => self.error_exit_code,
+            Outcome::ParseError => self.parse_error_exit_code,
+            Outcome::Untagged => self.untagged_exit_code,
+            Outcome::UnknownNumbers => self.unknown_numbers_exit_code,
+            Outcome::NoChanges => self.no_changes_exit_code,
+        };
+
+        let strict = self.strict.unwrap_or(false);
+        configured.unwrap_or(match outcome {
+            Outcome::Error | Outcome::ParseError => u8::from(strict),
+            Outcome::Untagged => u8::from(strict || self.untagged() == Policy::Fail),
+            Outcome::UnknownNumbers => u8::from(strict || self.unknown_numbers() == Policy::Fail),
+            Outcome::NoChanges => 0,
+        })
+    }
+
+    /// Name of the trailer, `Tests` if not configured.
+    pub fn trailer(&self) -> &str {
+        self.trailer.as_deref().unwrap_or("Tests")
+    }
+
+    /// Ref of the git notes, `refs/notes/tests` if not configured.
+    pub fn notes_ref(&self) -> &str {
+        self.notes_ref.as_deref().unwrap_or(DEFAULT_NOTES_REF)
+    }
+
+    /// Format of the git notes, the trailer if not configured.
+    pub fn notes_format(&self) -> NoteFormat {
+        self.notes_format.unwrap_or_default()
+    }
+
+    /// Maximum width of a trailer line, 72 if not configured.
+    pub fn width(&self) -> usize {
+        self.width.unwrap_or(72)
+    }
+
+    /// Policy for untagged scenarios, [`Policy::Ignore`] if not configured.
+    pub fn untagged(&self) -> Policy {
+        self.untagged.unwrap_or_default()
+    }
+
+    /// Policy for numbers rejected by the catalog, [`Policy::Warn`] if not configured.
+    ///
+    /// Always [`Policy::Ignore`] without a catalog.
+    pub fn unknown_numbers(&self) -> Policy {
+        if self.catalog.is_none() {
+            return Policy::Ignore;
+        }
+        self.unknown_numbers.unwrap_or(Policy::Warn)
+    }
+}
+
+fn git_key(name: &str) -> String {
+    format!("{GIT_CONFIG_SECTION}.{name}")
+}
+
+fn git_string(config: &git2::Config, name: &str) -> Result<Option<String>, ConfigError> {
+    match config.get_string(&git_key(name)) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Read a key that is converted from a string, like a number.
+fn git_parsed<T: FromStr>(config: &git2::Config, name: &str) -> Result<Option<T>, ConfigError> {
+    git_string(config, name)?
+        .map(|value| {
+            value.parse().map_err(|_| ConfigError::InvalidValue {
+                key: git_key(name),
+                value,
+            })
+        })
+        .transpose()
+}
+
+/// Read a key with one of the values of an enum, ignoring case.
+fn git_enum<T: clap::ValueEnum>(
+    config: &git2::Config,
+    name: &str,
+) -> Result<Option<T>, ConfigError> {
+    git_string(config, name)?
+        .map(|value| {
+            T::from_str(&value, true).map_err(|_| ConfigError::InvalidValue {
+                key: git_key(name),
+                value,
+            })
+        })
+        .transpose()
+}
+
+/// Fail for languages without Gherkin keywords.
+fn check_language(key: &str, language: &str) -> Result<(), ConfigError> {
+    if gherkin::is_language_supported(language) {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidValue {
+            key: key.to_owned(),
+            value: language.to_owned(),
+        })
+    }
+}
+
+fn git_bool(config: &git2::Config, name: &str) -> Result<Option<bool>, ConfigError> {
+    match config.get_bool(&git_key(name)) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(_) => Err(ConfigError::InvalidValue {
+            key: git_key(name),
+            value: git_string(config, name)?.unwrap_or_default(),
+        }),
+    }
+}
+
+/// Read a key that may be given multiple times.
+fn git_strings(config: &git2::Config, name: &str) -> Result<Option<Vec<String>>, ConfigError> {
+    let mut values = Vec::new();
+    let mut entries = config.multivar(&git_key(name), None)?;
+    while let Some(entry) = entries.next() {
+        if let Some(value) = entry?.value() {
+            values.push(value.to_owned());
+        }
+    }
+
+    Ok((!values.is_empty()).then_some(values))
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(value: io::Error) -> Self {
+        ConfigError::Io(value)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(value: toml::de::Error) -> Self {
+        ConfigError::Toml(value)
+    }
+}
+
+impl From<git2::Error> for ConfigError {
+    fn from(value: git2::Error) -> Self {
+        ConfigError::Git(value)
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(_) => write!(f, "Failed to read {CONFIG_FILE}"),
+            ConfigError::Toml(_) => write!(f, "Invalid {CONFIG_FILE}"),
+            ConfigError::Git(_) => write!(f, "Failed to read git config"),
+            ConfigError::InvalidValue { key, value } => {
+                write!(f, "Invalid value '{value}' for '{key}'")
+            }
+            ConfigError::MissingValue { key } => write!(f, "Missing value for '{key}'"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Io(error) => Some(error),
+            ConfigError::Toml(error) => Some(error),
+            ConfigError::Git(error) => Some(error),
+            ConfigError::InvalidValue { .. } | ConfigError::MissingValue { .. } => None,
+        }
+    }
+}
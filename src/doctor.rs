@@ -0,0 +1,305 @@
+//! Diagnostics for a repository where the trailer does not show up.
+//!
+//! Each check looks at one reason why no tracking numbers could be added:
+//! the hook is missing, pre-commit does not install the hook stage,
+//! the configuration is invalid, the prefix matches no tags or the test files can't be parsed.
+
+use std::{fmt::Display, fs, path::Path};
+
+use git2::Repository;
+
+use crate::{
+    config::{Config, CONFIG_FILE},
+    install::{Hook, HookStatus},
+    scan::{scan, Revision},
+};
+
+/// Name of the configuration file of pre-commit.
+pub const PRE_COMMIT_CONFIG: &str = ".pre-commit-config.yaml";
+
+/// The result of a single check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    /// What was checked, e.g. `hook`.
+    pub topic: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// How bad the result of a check is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Everything works as expected.
+    Ok,
+    /// Probably works, but may be unintended.
+    Warning,
+    /// No tracking numbers are added because of this.
+    Problem,
+}
+
+/// Run all checks.
+///
+/// `overrides` are the settings given on the command line,
+/// which take precedence over the configuration of the repository.
+pub fn diagnose(repo: &Repository, overrides: Config) -> Vec<Check> {
+    let pre_commit = pre_commit_config(repo);
+    let mut checks = check_hook(repo, pre_commit.as_deref());
+    checks.extend(pre_commit.as_deref().and_then(check_pre_commit));
+
+    let command_line = overrides != Config::default();
+    let config = match Config::load(repo) {
+        Ok(config) => config.merge(overrides),
+        Err(err) => {
+            checks.push(Check::new("config", Severity::Problem, err.to_string()));
+            return checks;
+        }
+    };
+    checks.push(describe_config(repo, &config, command_line));
+    checks.extend(check_tests(repo, &config));
+
+    checks
+}
+
+/// Whether git runs `show-changed-tests` as hook.
+fn check_hook(repo: &Repository, pre_commit: Option<&str>) -> Vec<Check> {
+    let hook = match Hook::locate(repo) {
+        Ok(hook) => hook,
+        Err(err) => return vec![Check::new("hook", Severity::Problem, err.to_string())],
+    };
+    let path = hook.path().display();
+
+    let status = match hook.status() {
+        Ok(status) => status,
+        Err(err) => return vec![Check::new("hook", Severity::Problem, err.to_string())],
+    };
+    let check = match status {
+        HookStatus::Missing => Check::new(
+            "hook",
+            Severity::Problem,
+            format!("{path} does not exist, run `show-changed-tests install`"),
+        ),
+        HookStatus::Installed { chained: false } => {
+            Check::new("hook", Severity::Ok, format!("{path} is installed"))
+        }
+        HookStatus::Installed { chained: true } => Check::new(
+            "hook",
+            Severity::Ok,
+            format!(
+                "{path} is installed and runs {} first",
+                hook.chained_path().display()
+            ),
+        ),
+        HookStatus::Foreign => {
+            let script = fs::read_to_string(hook.path()).unwrap_or_default();
+            if script.contains("show-changed-tests") && !script.contains("\"$@\"") {
+                Check::new(
+                    "hook",
+                    Severity::Problem,
+                    format!(
+                        "{path} does not pass the arguments of git, call `show-changed-tests -- \"$@\"`"
+                    ),
+                )
+            } else if script.contains("show-changed-tests") {
+                Check::new(
+                    "hook",
+                    Severity::Ok,
+                    format!("{path} calls show-changed-tests"),
+                )
+            } else if script.contains("pre-commit")
+                && pre_commit.is_some_and(|config| config.contains("show-changed-tests"))
+            {
+                Check::new(
+                    "hook",
+                    Severity::Ok,
+                    format!("{path} is installed by pre-commit"),
+                )
+            } else if script.contains("pre-commit") {
+                Check::new(
+                    "hook",
+                    Severity::Warning,
+                    format!(
+                        "{path} is installed by pre-commit, but {PRE_COMMIT_CONFIG} does not use show-changed-tests"
+                    ),
+                )
+            } else {
+                Check::new(
+                    "hook",
+                    Severity::Problem,
+                    format!(
+                        "{path} does not call show-changed-tests, run `show-changed-tests install` to chain it"
+                    ),
+                )
+            }
+        }
+    };
+
+    let mut checks = vec![check];
+    if status != HookStatus::Missing && !is_executable(hook.path()) {
+        checks.push(Check::new(
+            "hook",
+            Severity::Problem,
+            format!("{path} is not executable, so git ignores it"),
+        ));
+    }
+    checks
+}
+
+/// Contents of the pre-commit configuration, if the repository has one.
+fn pre_commit_config(repo: &Repository) -> Option<String> {
+    fs::read_to_string(repo.workdir()?.join(PRE_COMMIT_CONFIG)).ok()
+}
+
+/// Whether pre-commit installs the `prepare-commit-msg` stage, if it is used for the hook.
+fn check_pre_commit(config: &str) -> Option<Check> {
+    if !config.contains("show-changed-tests") {
+        return None;
+    }
+
+    // A YAML parser would be overkill, the stage name is unique enough
+    Some(if config.contains("prepare-commit-msg") {
+        Check::new(
+            "pre-commit",
+            Severity::Ok,
+            format!("{PRE_COMMIT_CONFIG} installs the prepare-commit-msg stage"),
+        )
+    } else {
+        Check::new(
+            "pre-commit",
+            Severity::Problem,
+            format!(
+                "{PRE_COMMIT_CONFIG} does not install the prepare-commit-msg stage, \
+                add `default_install_hook_types: [pre-commit, prepare-commit-msg]` \
+                and run `pre-commit install`"
+            ),
+        )
+    })
+}
+
+/// The effective settings and where they come from.
+fn describe_config(repo: &Repository, config: &Config, command_line: bool) -> Check {
+    let opts = config.options();
+    let file_exists = repo
+        .workdir()
+        .is_some_and(|dir| dir.join(CONFIG_FILE).exists());
+    let git_keys = repo
+        .config()
+        .ok()
+        .and_then(|config| Config::from_git_config(&config).ok())
+        .is_some_and(|git| git != Config::default());
+
+    let sources: Vec<&str> = [
+        (file_exists, CONFIG_FILE),
+        (git_keys, "git config"),
+        (command_line, "command line"),
+    ]
+    .into_iter()
+    .filter_map(|(used, source)| used.then_some(source))
+    .collect();
+    let sources = if sources.is_empty() {
+        "defaults".to_owned()
+    } else {
+        sources.join(", ")
+    };
+
+    Check::new(
+        "config",
+        Severity::Ok,
+        format!(
+            "prefix '{}', trailer '{}', include {}, exclude {}, language {} (from {sources})",
+            opts.test_prefix,
+            config.trailer(),
+            List(&opts.include),
+            List(&opts.exclude),
+            opts.language(),
+        ),
+    )
+}
+
+/// How many test cases have a tracking number, and which files can't be parsed.
+fn check_tests(repo: &Repository, config: &Config) -> Vec<Check> {
+    let opts = config.options();
+    let scanned = match scan(repo, &Revision::Index, &opts) {
+        Ok(scanned) => scanned,
+        Err(err) => return vec![Check::new("tests", Severity::Problem, err.to_string())],
+    };
+
+    let total = scanned.tests.len();
+    let tagged = scanned.tests.iter().filter(|t| t.number.is_some()).count();
+    let prefix = &opts.test_prefix;
+    let mut checks = vec![if scanned.files == 0 {
+        Check::new(
+            "tests",
+            Severity::Problem,
+            format!("No staged file matches include {}", List(&opts.include)),
+        )
+    } else if tagged == 0 {
+        Check::new(
+            "tests",
+            Severity::Problem,
+            format!("None of the {total} test cases has a tag with prefix '{prefix}'"),
+        )
+    } else {
+        Check::new(
+            "tests",
+            Severity::Ok,
+            format!(
+                "{tagged} of {total} test cases in {} {} have a tag with prefix '{prefix}'",
+                scanned.files,
+                if scanned.files == 1 { "file" } else { "files" }
+            ),
+        )
+    }];
+
+    checks.extend(
+        scanned
+            .warnings
+            .iter()
+            .map(|warning| Check::new("parse", Severity::Problem, warning.to_string())),
+    );
+    checks
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|meta| meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+    true
+}
+
+impl Check {
+    fn new(topic: &'static str, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            topic,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self.severity {
+            Severity::Ok => "ok",
+            Severity::Warning => "warning",
+            Severity::Problem => "problem",
+        };
+        write!(f, "[{label}] {}: {}", self.topic, self.message)
+    }
+}
+
+/// Glob patterns as a comma separated list.
+struct List<'a>(&'a [String]);
+
+impl Display for List<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("(none)");
+        }
+        f.write_str(&self.0.join(", "))
+    }
+}
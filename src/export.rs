@@ -0,0 +1,68 @@
+//! The test cases changed in a revision range, for import into test management tools.
+//!
+//! The changes of every commit are detected like for `history`,
+//! the test cases themselves are read from the end of the range,
+//! so the export contains their latest name and steps.
+
+use git2::{Oid, Repository};
+
+use crate::{
+    history::{last_changes, range_end, HistoryError, NumberCache},
+    scan::{scan, Revision, ScannedTest},
+    Options, Warning,
+};
+
+/// The changed test cases of a range.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Export {
+    /// Every changed test case with a tracking number, sorted by number.
+    pub tests: Vec<ExportedTest>,
+    /// Files at the end of the range that could not be parsed.
+    pub warnings: Vec<Warning>,
+}
+
+/// A test case changed in the range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedTest {
+    /// The test case at the end of the range.
+    pub test: ScannedTest,
+    /// The newest commit of the range that changed the test case.
+    pub last_commit: Oid,
+    /// When [`ExportedTest::last_commit`] was created.
+    pub last_modified: git2::Time,
+}
+
+/// All test cases with a tracking number that were changed by a commit in `range`.
+///
+/// Test cases that no longer exist at the end of the range are left out.
+pub fn changed_in_range(
+    repo: &Repository,
+    range: &str,
+    opts: &Options,
+    cache: &mut NumberCache,
+) -> Result<Export, HistoryError> {
+    let last_changes = last_changes(repo, range, opts, cache)?;
+
+    let end = range_end(repo, range)?;
+    let scanned =
+        scan(repo, &Revision::Commit(end.id().to_string()), opts).map_err(HistoryError::Detect)?;
+
+    let mut tests: Vec<_> = scanned
+        .tests
+        .into_iter()
+        .filter_map(|test| {
+            let change = last_changes.get(&test.number?)?;
+            Some(ExportedTest {
+                test,
+                last_commit: change.commit,
+                last_modified: change.time,
+            })
+        })
+        .collect();
+    tests.sort_by_key(|exported| exported.test.number);
+
+    Ok(Export {
+        tests,
+        warnings: scanned.warnings,
+    })
+}
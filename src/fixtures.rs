@@ -0,0 +1,176 @@
+//! Propagation of changed data files to the scenarios that use them.
+//!
+//! Scenarios often reference fixture files, e.g. `Given the payload "fixtures/withdraw.json"`.
+//! A change to such a file is a change of the scenario, which is detected in two ways:
+//!
+//! - Explicitly, via [`Options::fixtures`], which maps glob patterns to tracking numbers
+//! - Automatically, if [`Options::fixture_references`] is enabled,
+//!   by looking for quoted paths in the steps, doc strings and tables of the staged features
+
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+};
+
+use gherkin::Step;
+use git2::{Diff, Repository};
+
+use crate::{
+    build_glob_set, changed_lines, changed_scenario, formats, new_features, parse_new_feature,
+    ChangeKind, ChangedLine, ChangedTest, ExtractNumberError, Options, PathFilter, Versions,
+    Warning,
+};
+
+/// Test cases that use a file changed in the diff.
+pub(crate) fn changed_fixture_usages(
+    repo: &Repository,
+    versions: &Versions,
+    diff: &Diff,
+    opts: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<ChangedTest>, ExtractNumberError> {
+    let mut tests = Vec::new();
+
+    if opts.fixtures.is_empty() && !opts.fixture_references {
+        return Ok(tests);
+    }
+
+    // Changes to the test files themselves are handled elsewhere
+    let test_filter = PathFilter::new(opts)?;
+    let changed_files: BTreeSet<PathBuf> = diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .filter(|path| !test_filter.matches(path))
+        .map(ToOwned::to_owned)
+        .collect();
+
+    if changed_files.is_empty() {
+        return Ok(tests);
+    }
+
+    let all_files = PathFilter::from_patterns(&["**".to_owned()], &[])?;
+    let fixture_lines = changed_lines(diff, &all_files);
+    let lines_of = |file: &Path| -> Vec<ChangedLine> {
+        fixture_lines
+            .iter()
+            .filter(|line| line.path == file)
+            .cloned()
+            .collect()
+    };
+
+    for (pattern, fixture_numbers) in &opts.fixtures {
+        let glob = build_glob_set(std::slice::from_ref(pattern))?;
+        for file in changed_files.iter().filter(|path| glob.is_match(path)) {
+            tests.extend(fixture_numbers.iter().map(|&number| ChangedTest {
+                number: Some(number),
+                test: None,
+                kind: ChangeKind::Fixture { path: file.clone() },
+                lines: lines_of(file),
+            }));
+        }
+    }
+
+    if !opts.fixture_references {
+        return Ok(tests);
+    }
+
+    for (path, text) in new_features(repo, versions, opts)? {
+        let Some(feature) = parse_new_feature(&path, &text, opts, warnings) else {
+            continue;
+        };
+        let base = path.parent().unwrap_or(Path::new(""));
+        let references = |steps: &[Step]| -> BTreeSet<PathBuf> {
+            steps
+                .iter()
+                .flat_map(referenced_paths)
+                .filter_map(|reference| resolve(&reference, base, &changed_files))
+                .collect()
+        };
+
+        for (backgrounds, scenarios) in formats::scenario_groups(&feature) {
+            let background_references: BTreeSet<_> = backgrounds
+                .iter()
+                .flat_map(|steps| references(steps))
+                .collect();
+            for scenario in scenarios {
+                let mut files = references(&scenario.steps);
+                files.extend(background_references.iter().cloned());
+
+                for file in files {
+                    let lines = lines_of(&file);
+                    let kind = ChangeKind::Fixture { path: file };
+                    tests.push(changed_scenario(&path, scenario, opts, kind, lines));
+                }
+            }
+        }
+    }
+
+    Ok(tests)
+}
+
+/// Strings in a step that may be paths: quoted text, table cells and doc string lines.
+fn referenced_paths(step: &Step) -> Vec<String> {
+    let mut candidates = quoted_strings(&step.value);
+
+    if let Some(docstring) = &step.docstring {
+        candidates.extend(quoted_strings(docstring));
+        candidates.extend(docstring.lines().map(|line| line.trim().to_owned()));
+    }
+
+    if let Some(table) = &step.table {
+        candidates.extend(table.rows.iter().flatten().cloned());
+    }
+
+    candidates.retain(|candidate| !candidate.is_empty());
+    candidates
+}
+
+/// Contents of all strings in single or double quotes.
+///
+/// Quotes directly after a letter are apostrophes, like in `Alice's`, and do not start a string.
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = &rest[start..start + 1];
+        let is_apostrophe = rest[..start]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_alphanumeric);
+        if is_apostrophe {
+            rest = &rest[start + 1..];
+            continue;
+        }
+
+        let Some(len) = rest[start + 1..].find(quote) else {
+            break;
+        };
+        strings.push(rest[start + 1..start + 1 + len].to_owned());
+        rest = &rest[start + len + 2..];
+    }
+    strings
+}
+
+/// The changed file the reference points to, relative to the repository root or the feature file.
+fn resolve(reference: &str, base: &Path, changed_files: &BTreeSet<PathBuf>) -> Option<PathBuf> {
+    let reference = Path::new(reference);
+    [normalize(reference), normalize(&base.join(reference))]
+        .into_iter()
+        .find(|path| changed_files.contains(path))
+}
+
+/// Resolve `.` and `..` without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+    result
+}
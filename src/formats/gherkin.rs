@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use ::gherkin::{Feature, GherkinEnv, Scenario, Step};
+
+use super::{ParseError, SharedSection, TestFile, TestFormat, TestItem};
+
+/// Gherkin feature files.
+///
+/// Each scenario is a test case, backgrounds are shared by the scenarios
+/// of their feature or rule.
+///
+/// The keywords are taken from the `# language:` header of the file,
+/// files without header use the language given to [`Gherkin::new`].
+#[derive(Debug, Clone)]
+pub struct Gherkin {
+    language: String,
+}
+
+/// Language of the keywords if neither the file nor the configuration specify one.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Extensions of Gherkin files, `story` is used by JBehave.
+const EXTENSIONS: &[&str] = &["feature", "story"];
+
+impl Gherkin {
+    /// Parse files without `# language:` header with the keywords of `language`, e.g. `de`.
+    pub fn new(language: impl Into<String>) -> Self {
+        Self {
+            language: language.into(),
+        }
+    }
+
+    /// Language of files without `# language:` header.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+}
+
+impl Default for Gherkin {
+    fn default() -> Self {
+        Self::new(DEFAULT_LANGUAGE)
+    }
+}
+
+impl TestFormat for Gherkin {
+    fn name(&self) -> &str {
+        "gherkin"
+    }
+
+    /// Files with a `feature` or `story` extension, also followed by others like `.feature.md`.
+    fn handles(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                name.split('.')
+                    .skip(1)
+                    .any(|extension| EXTENSIONS.contains(&extension))
+            })
+    }
+
+    fn parse(&self, text: &str) -> Result<TestFile, ParseError> {
+        let feature = parse_feature(text, &self.language)?;
+
+        let mut file = TestFile {
+            feature: Some(feature.name.clone()),
+            ..TestFile::default()
+        };
+
+        let feature_items = add_scenarios(&mut file, &feature.scenarios);
+        let mut all_items = feature_items.clone();
+
+        for rule in &feature.rules {
+            let rule_items = add_scenarios(&mut file, &rule.scenarios);
+            all_items.extend(&rule_items);
+
+            if let Some(background) = &rule.background {
+                file.shared.push(SharedSection {
+                    name: section_name(&background.keyword, &background.name),
+                    span: background.span.start..background.span.end,
+                    items: rule_items,
+                });
+            }
+        }
+
+        if let Some(background) = &feature.background {
+            file.shared.push(SharedSection {
+                name: section_name(&background.keyword, &background.name),
+                span: background.span.start..background.span.end,
+                items: all_items,
+            });
+        }
+
+        Ok(file)
+    }
+
+    fn as_gherkin(&self) -> Option<&Gherkin> {
+        Some(self)
+    }
+}
+
+/// Parse a feature in the language of its `# language:` header, or `default_language`.
+pub(crate) fn parse_feature(text: &str, default_language: &str) -> Result<Feature, ParseError> {
+    let (language, text) = match language_header(text) {
+        Some((language, header)) => {
+            // The header is replaced by spaces, so the spans still match the original text.
+            // The parser would otherwise also read it, but without trimming trailing whitespace.
+            let blank = " ".repeat(header.len());
+            (language, text.replacen(header, &blank, 1))
+        }
+        None => (default_language, text.to_owned()),
+    };
+
+    if !::gherkin::is_language_supported(language) {
+        return Err(ParseError::new(format!(
+            "Unsupported Gherkin language '{language}'"
+        )));
+    }
+    let env = GherkinEnv::new(language).map_err(|e| ParseError::new(e.to_string()))?;
+
+    Feature::parse(&text, env).map_err(|e| ParseError::new(e.to_string()))
+}
+
+/// The language and the complete line of a `# language: de` header.
+///
+/// Like in Gherkin, the header has to be the first line that is neither empty nor a comment.
+pub(crate) fn language_header(text: &str) -> Option<(&str, &str)> {
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let comment = trimmed.strip_prefix('#')?;
+        let Some((key, language)) = comment.split_once(':') else {
+            continue;
+        };
+        if key.trim() == "language" {
+            return Some((language.trim(), line));
+        }
+    }
+    None
+}
+
+/// Name of a background, which is often empty, together with its keyword.
+fn section_name(keyword: &str, name: &str) -> String {
+    if name.is_empty() {
+        keyword.to_owned()
+    } else {
+        format!("{keyword}: {name}")
+    }
+}
+
+/// Add the scenarios as items and return their indices.
+fn add_scenarios(file: &mut TestFile, scenarios: &[Scenario]) -> Vec<usize> {
+    scenarios
+        .iter()
+        .map(|scenario| {
+            file.items.push(TestItem {
+                name: scenario.name.clone(),
+                line: scenario.position.line,
+                span: scenario.span.start..scenario.span.end,
+                tags: scenario.tags.clone(),
+            });
+            file.items.len() - 1
+        })
+        .collect()
+}
+
+/// Scenarios of the feature and its rules, together with the steps of their backgrounds.
+pub(crate) fn scenario_groups(feature: &Feature) -> Vec<(Vec<&[Step]>, &[Scenario])> {
+    let feature_background: Vec<_> = feature.background.iter().map(|b| &b.steps[..]).collect();
+
+    let mut groups = vec![(feature_background.clone(), &feature.scenarios[..])];
+    for rule in &feature.rules {
+        let mut backgrounds = feature_background.clone();
+        backgrounds.extend(rule.background.iter().map(|b| &b.steps[..]));
+        groups.push((backgrounds, &rule.scenarios[..]));
+    }
+
+    groups
+}
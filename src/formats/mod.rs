@@ -0,0 +1,167 @@
+//! Extraction of test cases from the different kinds of test files.
+//!
+//! Each supported file type implements [`TestFormat`],
+//! which turns the contents of a file into a list of [`TestItem`]s.
+//! The mapping from changed lines to tracking numbers is the same for all formats.
+
+use std::{fmt::Display, ops::Range, path::Path};
+
+use crate::steps::StepDefinition;
+
+mod gherkin;
+mod pytest;
+mod robot;
+mod rust;
+
+pub(crate) use self::gherkin::{parse_feature, scenario_groups};
+pub use self::{
+    gherkin::{Gherkin, DEFAULT_LANGUAGE},
+    pytest::Pytest,
+    robot::Robot,
+    rust::Rust,
+};
+
+/// A kind of test file, e.g. Gherkin features.
+pub trait TestFormat {
+    /// Short name of the format, used in messages.
+    fn name(&self) -> &str;
+
+    /// Whether files at this path are handled by this format.
+    fn handles(&self, path: &Path) -> bool;
+
+    /// Extract the test cases from the contents of a file.
+    fn parse(&self, text: &str) -> Result<TestFile, ParseError>;
+
+    /// The format as [`Gherkin`], `None` for all other formats.
+    fn as_gherkin(&self) -> Option<&Gherkin> {
+        None
+    }
+}
+
+/// All test cases found in a single file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestFile {
+    /// Name of the feature the test cases belong to, only known for Gherkin.
+    pub feature: Option<String>,
+    pub items: Vec<TestItem>,
+    /// Parts of the file that belong to multiple test cases.
+    pub shared: Vec<SharedSection>,
+}
+
+/// A single test case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestItem {
+    pub name: String,
+    /// Line where the test case starts, 1 based.
+    pub line: usize,
+    /// Byte range of the test case in the file.
+    pub span: Range<usize>,
+    /// Tags of the test case, in the form `<prefix><number>` for tracking numbers.
+    pub tags: Vec<String>,
+}
+
+/// A part of the file that affects multiple test cases, like a Gherkin background.
+///
+/// A change inside the section is a change of every listed test case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedSection {
+    pub name: String,
+    /// Byte range of the section in the file.
+    pub span: Range<usize>,
+    /// Indices into [`TestFile::items`] of the affected test cases.
+    pub items: Vec<usize>,
+}
+
+/// A file could not be parsed by its format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+}
+
+/// Step definitions in a file with Rust or Python code.
+pub(crate) fn step_definitions(path: &Path, text: &str) -> Vec<StepDefinition> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("rs") => rust::step_definitions(text),
+        Some("py") => pytest::step_definitions(text),
+        _ => Vec::new(),
+    }
+}
+
+/// The formats that are used if nothing else is configured.
+///
+/// Feature files without `# language:` header are parsed with the keywords of `language`.
+pub fn default_formats(language: &str) -> Vec<Box<dyn TestFormat>> {
+    vec![
+        Box::new(Pytest),
+        Box::new(Robot),
+        Box::new(Rust),
+        Box::new(Gherkin::new(language)),
+    ]
+}
+
+/// A line of a file, with its position.
+struct SourceLine<'a> {
+    /// Text of the line without the line break.
+    text: &'a str,
+    /// Byte offset of the start of the line.
+    start: usize,
+    /// Byte offset of the end of the line, including the line break.
+    end: usize,
+}
+
+fn source_lines(text: &str) -> Vec<SourceLine<'_>> {
+    let mut start = 0;
+    text.split_inclusive('\n')
+        .map(|line| {
+            let source_line = SourceLine {
+                text: line.trim_end_matches(['\n', '\r']),
+                start,
+                end: start + line.len(),
+            };
+            start += line.len();
+            source_line
+        })
+        .collect()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Change of the bracket nesting depth in a piece of code.
+fn paren_depth(code: &str) -> isize {
+    code.chars().fold(0, |depth, c| match c {
+        '(' | '[' | '{' => depth + 1,
+        ')' | ']' | '}' => depth - 1,
+        _ => depth,
+    })
+}
+
+/// The first tracking number in the tags.
+pub(crate) fn tracking_number(tags: &[String], prefix: &str) -> Option<u32> {
+    tags.iter()
+        .find_map(|tag| tag.strip_prefix(prefix)?.parse().ok())
+}
+
+impl TestItem {
+    /// The tracking number of this test case, if it has one.
+    pub fn number(&self, prefix: &str) -> Option<u32> {
+        tracking_number(&self.tags, prefix)
+    }
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
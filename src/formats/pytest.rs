@@ -0,0 +1,264 @@
+use std::{ops::Range, path::Path};
+
+use gherkin::StepType;
+
+use super::{
+    indentation, paren_depth, source_lines, ParseError, SourceLine, TestFile, TestFormat, TestItem,
+};
+use crate::steps::{StepDefinition, StepPattern};
+
+/// Python test functions, as collected by pytest.
+///
+/// Every function whose name starts with `test` is a test case.
+/// Markers like `@pytest.mark.tc(123)` are turned into the tag `tc:123`,
+/// markers without arguments like `@pytest.mark.smoke` into the tag `smoke`.
+///
+/// The span of a test includes its decorators,
+/// so changing a marker is also a change of the test.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pytest;
+
+impl TestFormat for Pytest {
+    fn name(&self) -> &str {
+        "pytest"
+    }
+
+    fn handles(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == "py")
+    }
+
+    fn parse(&self, text: &str) -> Result<TestFile, ParseError> {
+        let items = functions(text)
+            .into_iter()
+            .filter(|function| function.name.starts_with("test"))
+            .map(|function| TestItem {
+                tags: function
+                    .decorators
+                    .iter()
+                    .flat_map(|d| marker_tags(d))
+                    .collect(),
+                name: function.name,
+                line: function.line,
+                span: function.span,
+            })
+            .collect();
+
+        Ok(TestFile {
+            feature: None,
+            items,
+            shared: Vec::new(),
+        })
+    }
+}
+
+/// Step definitions of behave or pytest-bdd, like `@given(parsers.parse("..."))`.
+pub(super) fn step_definitions(text: &str) -> Vec<StepDefinition> {
+    functions(text)
+        .into_iter()
+        .flat_map(|function| {
+            function
+                .decorators
+                .iter()
+                .filter_map(|decorator| step_decorator(decorator))
+                .map(|(step_type, pattern)| StepDefinition {
+                    function: function.name.clone(),
+                    line: function.line,
+                    span: function.span.clone(),
+                    step_type,
+                    pattern,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// A function definition.
+struct Function {
+    name: String,
+    /// Line of the first decorator or the definition, 1 based.
+    line: usize,
+    /// Byte range of the function, including decorators.
+    span: Range<usize>,
+    /// Decorators without the leading `@`.
+    decorators: Vec<String>,
+}
+
+fn functions(text: &str) -> Vec<Function> {
+    let lines = source_lines(text);
+    let mut functions = Vec::new();
+
+    // Decorators seen since the last statement, with the index of the first one
+    let mut decorators: Option<(usize, Vec<String>)> = None;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = &lines[idx];
+        let code = line.text.trim();
+
+        if code.is_empty() || code.starts_with('#') {
+            idx += 1;
+            continue;
+        }
+
+        if let Some(decorator) = code.strip_prefix('@') {
+            let first = idx;
+            // Decorators can span multiple lines when they have arguments
+            let mut decorator = decorator.to_owned();
+            let mut depth = paren_depth(&decorator);
+            while depth > 0 && idx + 1 < lines.len() {
+                idx += 1;
+                let next = lines[idx].text.trim();
+                depth += paren_depth(next);
+                decorator.push_str(next);
+            }
+            decorators
+                .get_or_insert_with(|| (first, Vec::new()))
+                .1
+                .push(decorator);
+            idx += 1;
+            continue;
+        }
+
+        let decorated = decorators.take();
+
+        let Some(name) = function_name(code) else {
+            idx += 1;
+            continue;
+        };
+
+        let (start_line, decorators) = decorated.unwrap_or((idx, Vec::new()));
+        let last = body_end(&lines, idx);
+
+        functions.push(Function {
+            name: name.to_owned(),
+            line: start_line + 1,
+            span: lines[start_line].start..lines[last].end,
+            decorators,
+        });
+
+        idx += 1;
+    }
+
+    functions
+}
+
+/// Name of the function if the line defines a function.
+fn function_name(code: &str) -> Option<&str> {
+    let code = code.strip_prefix("async ").unwrap_or(code).trim_start();
+    let rest = code.strip_prefix("def ")?.trim_start();
+
+    Some(rest[..rest.find('(')?].trim_end())
+}
+
+/// Index of the last line belonging to the function defined in line `def`.
+///
+/// The body ends before the first line that is not indented deeper than the definition.
+/// Trailing blank lines and comments are not part of the body.
+fn body_end(lines: &[SourceLine], def: usize) -> usize {
+    let def_indent = indentation(lines[def].text);
+
+    let mut last = def;
+    for (idx, line) in lines.iter().enumerate().skip(def + 1) {
+        let code = line.text.trim();
+        if code.is_empty() {
+            continue;
+        }
+        if indentation(line.text) <= def_indent {
+            if code.starts_with('#') {
+                continue;
+            }
+            break;
+        }
+        last = idx;
+    }
+
+    last
+}
+
+/// Tags for a decorator, without the leading `@`.
+fn marker_tags(decorator: &str) -> Vec<String> {
+    let Some(marker) = decorator
+        .strip_prefix("pytest.mark.")
+        .or_else(|| decorator.strip_prefix("mark."))
+    else {
+        return Vec::new();
+    };
+
+    let Some((name, args)) = marker.split_once('(') else {
+        return vec![marker.trim().to_owned()];
+    };
+    let name = name.trim();
+    let args = args.trim_end().strip_suffix(')').unwrap_or(args);
+
+    let values: Vec<_> = args
+        .split(',')
+        .map(|arg| arg.trim().trim_matches(['"', '\'']))
+        .filter(|arg| !arg.is_empty())
+        .collect();
+
+    if values.is_empty()
+        || values.iter().any(|arg| {
+            !arg.chars()
+                .all(|c| c.is_alphanumeric() || "_-.:".contains(c))
+        })
+    {
+        // Arguments that are not simple literals, e.g. of `parametrize`
+        return vec![name.to_owned()];
+    }
+
+    values
+        .iter()
+        .map(|value| format!("{name}:{value}"))
+        .collect()
+}
+
+/// Step type and pattern of a step decorator, without the leading `@`.
+fn step_decorator(decorator: &str) -> Option<(Option<StepType>, StepPattern)> {
+    let (path, args) = decorator.split_once('(')?;
+    let step_type = match path.trim().rsplit('.').next()? {
+        "given" => Some(StepType::Given),
+        "when" => Some(StepType::When),
+        "then" => Some(StepType::Then),
+        "step" => None,
+        _ => return None,
+    };
+
+    let args = args.trim_start();
+    let pattern = if let Some(parser) = args.strip_prefix("parsers.") {
+        let (parser, args) = parser.split_once('(')?;
+        let text = string_literal(args.trim_start())?;
+        match parser.trim() {
+            "parse" | "cfparse" => StepPattern::Parse(text),
+            "re" => StepPattern::Regex(text),
+            "string" => StepPattern::Literal(text),
+            _ => return None,
+        }
+    } else {
+        // behave uses `parse` patterns by default, which also covers plain strings of pytest-bdd
+        StepPattern::Parse(string_literal(args)?)
+    };
+
+    Some((step_type, pattern))
+}
+
+/// Contents of the Python string literal at the start of `code`.
+fn string_literal(code: &str) -> Option<String> {
+    let (raw, code) = match code.strip_prefix(['r', 'R']) {
+        Some(code) => (true, code),
+        None => (false, code),
+    };
+    let quote = code.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+
+    let mut result = String::new();
+    let mut chars = code[1..].chars();
+    loop {
+        match chars.next()? {
+            c if c == quote => return Some(result),
+            '\\' if !raw => match chars.next()? {
+                'n' => result.push('\n'),
+                't' => result.push('\t'),
+                c => result.push(c),
+            },
+            c => result.push(c),
+        }
+    }
+}
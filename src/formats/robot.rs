@@ -0,0 +1,233 @@
+use std::path::Path;
+
+use super::{source_lines, ParseError, SharedSection, SourceLine, TestFile, TestFormat, TestItem};
+
+/// Robot Framework test suites.
+///
+/// Every entry in the `*** Test Cases ***` (or `*** Tasks ***`) section is a test case.
+/// Its tags are taken from the `[Tags]` setting,
+/// plus `Test Tags` / `Force Tags` and `Default Tags` from the `*** Settings ***` section.
+///
+/// Settings that apply to every test case, like `Test Setup` or `Test Tags`,
+/// are shared by all test cases of the file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Robot;
+
+/// Settings in the `*** Settings ***` section that affect every test case.
+const SHARED_SETTINGS: &[&str] = &[
+    "test setup",
+    "test teardown",
+    "test template",
+    "test timeout",
+    "test tags",
+    "force tags",
+    "default tags",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Settings,
+    TestCases,
+    Other,
+}
+
+impl TestFormat for Robot {
+    fn name(&self) -> &str {
+        "robot"
+    }
+
+    fn handles(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == "robot")
+    }
+
+    fn parse(&self, text: &str) -> Result<TestFile, ParseError> {
+        let lines = source_lines(text);
+
+        let mut file = TestFile::default();
+        let mut force_tags = Vec::new();
+        let mut default_tags = Vec::new();
+        // Own `[Tags]` of each item, `None` if the test has no `[Tags]` setting
+        let mut own_tags: Vec<Option<Vec<String>>> = Vec::new();
+
+        let mut section = None;
+        let mut idx = 0;
+        while idx < lines.len() {
+            let line = lines[idx].text;
+
+            if line.starts_with('*') {
+                section = Some(parse_section_header(line));
+                idx += 1;
+                continue;
+            }
+
+            if is_blank(line) {
+                idx += 1;
+                continue;
+            }
+
+            match section {
+                Some(Section::Settings) => {
+                    let last = continuation_end(&lines, idx);
+                    let cells = statement_cells(&lines[idx..=last]);
+                    let setting = cells.first().map(|c| c.to_lowercase()).unwrap_or_default();
+
+                    match setting.as_str() {
+                        "test tags" | "force tags" => force_tags.extend(tag_cells(&cells[1..])),
+                        "default tags" => default_tags.extend(tag_cells(&cells[1..])),
+                        _ => {}
+                    }
+
+                    if SHARED_SETTINGS.contains(&setting.as_str()) {
+                        file.shared.push(SharedSection {
+                            name: cells[0].clone(),
+                            span: lines[idx].start..lines[last].end,
+                            items: Vec::new(),
+                        });
+                    }
+
+                    idx = last + 1;
+                }
+                Some(Section::TestCases) if !starts_with_whitespace(line) => {
+                    let last = test_case_end(&lines, idx);
+                    let name = split_cells(line).into_iter().next().unwrap_or_default();
+
+                    file.items.push(TestItem {
+                        name: name.to_owned(),
+                        line: idx + 1,
+                        span: lines[idx].start..lines[last].end,
+                        tags: Vec::new(),
+                    });
+                    own_tags.push(test_case_tags(&lines[idx..=last]));
+
+                    idx = last + 1;
+                }
+                // Robot Framework ignores everything before the first section
+                _ => idx += 1,
+            }
+        }
+
+        // The test's own tags come first, so they take precedence for the tracking number
+        for (item, own) in file.items.iter_mut().zip(own_tags) {
+            item.tags = own.unwrap_or_else(|| default_tags.clone());
+            item.tags.extend(force_tags.iter().cloned());
+        }
+
+        let all_items: Vec<_> = (0..file.items.len()).collect();
+        for shared in &mut file.shared {
+            shared.items = all_items.clone();
+        }
+
+        Ok(file)
+    }
+}
+
+fn parse_section_header(line: &str) -> Section {
+    let name = line.trim().trim_matches('*').trim().to_lowercase();
+
+    match name.trim_end_matches('s') {
+        "setting" => Section::Settings,
+        "test case" | "task" => Section::TestCases,
+        _ => Section::Other,
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn starts_with_whitespace(line: &str) -> bool {
+    line.starts_with([' ', '\t'])
+}
+
+/// Split a line into its cells.
+///
+/// Cells are separated by two or more spaces or a tab.
+fn split_cells(line: &str) -> Vec<&str> {
+    let mut cells = Vec::new();
+    let mut rest = line.trim_end();
+    while !rest.is_empty() {
+        let separator = [rest.find("  "), rest.find('\t')]
+            .into_iter()
+            .flatten()
+            .min();
+        let Some(pos) = separator else {
+            cells.push(rest);
+            break;
+        };
+
+        cells.push(&rest[..pos]);
+        rest = rest[pos..].trim_start_matches([' ', '\t']);
+    }
+
+    cells.retain(|cell| !cell.is_empty());
+    cells
+}
+
+/// Index of the last line of the statement starting in line `first`,
+/// including continuation lines starting with `...`.
+fn continuation_end(lines: &[SourceLine], first: usize) -> usize {
+    let mut last = first;
+    for (idx, line) in lines.iter().enumerate().skip(first + 1) {
+        if is_blank(line.text) {
+            continue;
+        }
+        if split_cells(line.text).first() != Some(&"...") {
+            break;
+        }
+        last = idx;
+    }
+    last
+}
+
+/// All cells of a statement, without the continuation markers.
+fn statement_cells(lines: &[SourceLine]) -> Vec<String> {
+    lines
+        .iter()
+        .filter(|line| !is_blank(line.text))
+        .flat_map(|line| split_cells(line.text))
+        .filter(|cell| *cell != "...")
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Cells that are tags, skipping comments at the end of the line.
+fn tag_cells(cells: &[String]) -> Vec<String> {
+    cells
+        .iter()
+        .take_while(|cell| !cell.starts_with('#'))
+        .cloned()
+        .collect()
+}
+
+/// Index of the last non-blank line of the test case starting in line `first`.
+fn test_case_end(lines: &[SourceLine], first: usize) -> usize {
+    let mut last = first;
+    for (idx, line) in lines.iter().enumerate().skip(first + 1) {
+        if line.text.starts_with('*') {
+            break;
+        }
+        if is_blank(line.text) {
+            continue;
+        }
+        if !starts_with_whitespace(line.text) {
+            break;
+        }
+        last = idx;
+    }
+    last
+}
+
+/// The tags of the `[Tags]` setting of a test case, if it has one.
+fn test_case_tags(lines: &[SourceLine]) -> Option<Vec<String>> {
+    let is_tags = |cell: &&str| cell.eq_ignore_ascii_case("[tags]");
+
+    let start = lines
+        .iter()
+        .position(|line| split_cells(line.text).iter().any(is_tags))?;
+    let last = continuation_end(lines, start);
+    let cells = statement_cells(&lines[start..=last]);
+    let tags_idx = cells.iter().position(|cell| is_tags(&cell.as_str()))?;
+
+    Some(tag_cells(&cells[tags_idx + 1..]))
+}
@@ -0,0 +1,353 @@
+use std::{ops::Range, path::Path};
+
+use gherkin::StepType;
+
+use super::{paren_depth, source_lines, ParseError, SourceLine, TestFile, TestFormat, TestItem};
+use crate::steps::{StepDefinition, StepPattern};
+
+/// Rust test functions.
+///
+/// Every function with a `#[test]`, `#[tokio::test]` or `#[rstest]` attribute is a test case.
+/// Its tags are the words of the comments and doc attributes directly above the function,
+/// so `// tc:123` or `/// Regression test, tc:123` both tag the test with `tc:123`.
+///
+/// The span of a test includes its comments and attributes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rust;
+
+impl TestFormat for Rust {
+    fn name(&self) -> &str {
+        "rust"
+    }
+
+    fn handles(&self, path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == "rs")
+    }
+
+    fn parse(&self, text: &str) -> Result<TestFile, ParseError> {
+        let items = annotated_functions(text)?
+            .into_iter()
+            .filter(|function| function.preamble.is_test)
+            .map(|function| TestItem {
+                name: function.name,
+                line: function.preamble.first + 1,
+                span: function.span,
+                tags: function.preamble.tags,
+            })
+            .collect();
+
+        Ok(TestFile {
+            feature: None,
+            items,
+            shared: Vec::new(),
+        })
+    }
+}
+
+/// Step definitions of the `cucumber` crate, like `#[given(expr = "...")]`.
+pub(super) fn step_definitions(text: &str) -> Vec<StepDefinition> {
+    let Ok(functions) = annotated_functions(text) else {
+        return Vec::new();
+    };
+
+    functions
+        .into_iter()
+        .flat_map(|function| {
+            function
+                .preamble
+                .steps
+                .into_iter()
+                .map(move |(step_type, pattern)| StepDefinition {
+                    function: function.name.clone(),
+                    line: function.preamble.first + 1,
+                    span: function.span.clone(),
+                    step_type: Some(step_type),
+                    pattern,
+                })
+        })
+        .collect()
+}
+
+/// A function with comments or attributes.
+struct Function {
+    name: String,
+    /// Byte range of the function, including the preamble.
+    span: Range<usize>,
+    preamble: Preamble,
+}
+
+fn annotated_functions(text: &str) -> Result<Vec<Function>, ParseError> {
+    let lines = source_lines(text);
+    let mut functions = Vec::new();
+
+    // Comments and attributes directly above the current line
+    let mut preamble: Option<Preamble> = None;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let code = lines[idx].text.trim();
+
+        if code.starts_with("//") {
+            preamble
+                .get_or_insert_with(|| Preamble::new(idx))
+                .add_comment(code);
+            idx += 1;
+            continue;
+        }
+
+        if code.starts_with("#[") {
+            let first = idx;
+            // Attributes can span multiple lines when they have arguments
+            let mut attribute = code.to_owned();
+            let mut depth = paren_depth(code);
+            while depth > 0 && idx + 1 < lines.len() {
+                idx += 1;
+                let next = lines[idx].text.trim();
+                depth += paren_depth(next);
+                attribute.push_str(next);
+            }
+            preamble
+                .get_or_insert_with(|| Preamble::new(first))
+                .add_attribute(&attribute);
+            idx += 1;
+            continue;
+        }
+
+        let current = preamble.take();
+
+        let (Some(preamble), Some(name)) = (current, function_name(code)) else {
+            idx += 1;
+            continue;
+        };
+        if !preamble.is_test && preamble.steps.is_empty() {
+            idx += 1;
+            continue;
+        }
+
+        let last = body_end(&lines, idx, text)
+            .ok_or_else(|| ParseError::new(format!("Unclosed body of function '{name}'")))?;
+
+        functions.push(Function {
+            name: name.to_owned(),
+            span: lines[preamble.first].start..lines[last].end,
+            preamble,
+        });
+
+        idx = last + 1;
+    }
+
+    Ok(functions)
+}
+
+/// Comments and attributes in front of an item.
+struct Preamble {
+    /// Index of the first line.
+    first: usize,
+    is_test: bool,
+    tags: Vec<String>,
+    /// Step patterns of `cucumber` attributes.
+    steps: Vec<(StepType, StepPattern)>,
+}
+
+impl Preamble {
+    fn new(first: usize) -> Self {
+        Self {
+            first,
+            is_test: false,
+            tags: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    fn add_comment(&mut self, comment: &str) {
+        let text = comment.trim_start_matches('/').trim_start_matches('!');
+        self.add_words(text);
+    }
+
+    fn add_attribute(&mut self, attribute: &str) {
+        let inner = attribute
+            .trim_start_matches("#[")
+            .trim_end()
+            .trim_end_matches(']');
+        let path = inner.split(['(', '=']).next().unwrap_or_default().trim();
+
+        if path == "doc" {
+            let text = inner
+                .split_once('=')
+                .map(|(_, text)| text)
+                .unwrap_or_default();
+            self.add_words(text.trim().trim_matches('"'));
+        }
+
+        let last_segment = path.rsplit("::").next().unwrap_or_default();
+        let step_type = match last_segment {
+            "test" | "rstest" => {
+                self.is_test = true;
+                return;
+            }
+            "given" => StepType::Given,
+            "when" => StepType::When,
+            "then" => StepType::Then,
+            _ => return,
+        };
+
+        let args = inner[path.len()..].trim();
+        let args = args
+            .strip_prefix('(')
+            .and_then(|args| args.strip_suffix(')'))
+            .unwrap_or_default()
+            .trim();
+        let pattern = if let Some(expr) = named_argument(args, "expr") {
+            string_literal(expr).map(StepPattern::Expression)
+        } else if let Some(regex) = named_argument(args, "regex") {
+            string_literal(regex).map(StepPattern::Regex)
+        } else {
+            string_literal(args).map(StepPattern::Literal)
+        };
+
+        if let Some(pattern) = pattern {
+            self.steps.push((step_type, pattern));
+        }
+    }
+
+    fn add_words(&mut self, text: &str) {
+        self.tags.extend(
+            text.split_whitespace()
+                .map(|word| word.trim_start_matches('@'))
+                .map(|word| word.trim_end_matches(['.', ',', ';', ')']))
+                .filter(|word| !word.is_empty())
+                .map(ToOwned::to_owned),
+        );
+    }
+}
+
+/// Value of an argument like `expr = "..."`.
+fn named_argument<'a>(args: &'a str, name: &str) -> Option<&'a str> {
+    let value = args.strip_prefix(name)?.trim_start().strip_prefix('=')?;
+    Some(value.trim_start())
+}
+
+/// Contents of the string literal at the start of `code`, e.g. `"text"` or `r#"text"#`.
+fn string_literal(code: &str) -> Option<String> {
+    if let Some(raw) = code.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let contents = raw[hashes..].strip_prefix('"')?;
+        return Some(contents[..contents.find(&terminator)?].to_owned());
+    }
+
+    let mut result = String::new();
+    let mut chars = code.strip_prefix('"')?.chars();
+    loop {
+        match chars.next()? {
+            '"' => return Some(result),
+            '\\' => match chars.next()? {
+                'n' => result.push('\n'),
+                't' => result.push('\t'),
+                c => result.push(c),
+            },
+            c => result.push(c),
+        }
+    }
+}
+
+/// Keywords that can appear in front of `fn`.
+const QUALIFIERS: &[&str] = &[
+    "pub(crate)",
+    "pub(super)",
+    "pub",
+    "async",
+    "const",
+    "unsafe",
+    "extern \"C\"",
+    "extern",
+];
+
+/// Name of the function if the line starts a function definition.
+fn function_name(code: &str) -> Option<&str> {
+    let mut rest = code;
+    loop {
+        rest = rest.trim_start();
+        if let Some(name) = rest.strip_prefix("fn ") {
+            let name = name.trim_start();
+            let end = name.find(|c: char| !(c.is_alphanumeric() || c == '_'))?;
+            return Some(&name[..end]);
+        }
+
+        // Skip qualifiers like `pub(crate)`, `async` or `extern "C"`
+        let qualifier = QUALIFIERS.iter().find(|q| rest.starts_with(*q))?;
+        rest = &rest[qualifier.len()..];
+    }
+}
+
+/// Index of the line with the closing brace of the function starting in line `def`.
+fn body_end(lines: &[SourceLine], def: usize, text: &str) -> Option<usize> {
+    let start = lines[def].start;
+    let end = start + body_length(&text[start..])?;
+
+    lines.iter().rposition(|line| line.start < end)
+}
+
+/// Length of the function definition until the closing brace of its body.
+///
+/// Braces inside comments, strings and char literals are skipped.
+fn body_length(code: &str) -> Option<usize> {
+    let bytes = code.as_bytes();
+    let mut depth = 0;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                pos += code[pos..].find('\n')?;
+            }
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos += code[pos..].find("*/")? + 1;
+            }
+            b'r' if is_raw_string_start(code, pos) => {
+                let hashes = code[pos + 1..].len() - code[pos + 1..].trim_start_matches('#').len();
+                let terminator = format!("\"{}", "#".repeat(hashes));
+                pos += 2 + hashes;
+                pos += code[pos..].find(&terminator)? + terminator.len() - 1;
+            }
+            b'"' => {
+                pos += 1;
+                while bytes.get(pos)? != &b'"' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'\'' => {
+                // Char literal, unless it is a lifetime
+                if bytes.get(pos + 1) == Some(&b'\\') {
+                    pos += code.get(pos + 3..)?.find('\'')? + 3;
+                } else if let Some(c) = code[pos + 1..].chars().next() {
+                    let len = c.len_utf8();
+                    if bytes.get(pos + 1 + len) == Some(&b'\'') {
+                        pos += len + 1;
+                    }
+                }
+            }
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos + 1);
+                }
+            }
+            // A function without body, e.g. in a trait
+            b';' if depth == 0 => return Some(pos + 1),
+            _ => {}
+        }
+        pos += 1;
+    }
+
+    None
+}
+
+/// Whether a raw string literal like `r#"..."#` starts at `pos`.
+fn is_raw_string_start(code: &str, pos: usize) -> bool {
+    let before = code[..pos].chars().next_back();
+    if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+        return false;
+    }
+
+    code[pos + 1..].trim_start_matches('#').starts_with('"')
+}
@@ -0,0 +1,360 @@
+//! The commits of a revision range that changed test cases.
+//!
+//! Every commit is compared with its first parent, using the same detection as the hook.
+//! Parsing all test files of every commit is slow on long histories,
+//! so the numbers of each commit are kept in a cache in the git directory.
+//! Commits never change, the cache only has to be discarded when the configuration does.
+
+use std::{collections::BTreeMap, fmt::Display, fs, io, path::PathBuf};
+
+use git2::{Commit, Oid, Repository, RevparseMode, Sort};
+use serde::{Deserialize, Serialize};
+
+use crate::{changed_tests_in_commit, ExtractNumberError, Options};
+
+/// Name of the cache file in the git directory.
+pub const CACHE_FILE: &str = "show-changed-tests-cache.json";
+
+/// A commit that changed test cases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub id: Oid,
+    /// First line of the commit message.
+    pub summary: String,
+    pub author: String,
+    pub time: git2::Time,
+    /// Tracking numbers of the changed test cases, sorted.
+    pub numbers: Vec<u32>,
+}
+
+/// The last commit that changed a test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastChange {
+    pub commit: Oid,
+    pub time: git2::Time,
+}
+
+/// Tracking numbers changed by each commit, stored between runs.
+#[derive(Debug)]
+pub struct NumberCache {
+    /// Where the cache is stored, `None` to keep it in memory only.
+    path: Option<PathBuf>,
+    contents: CacheContents,
+    modified: bool,
+    /// Number of commits whose numbers were not cached and had to be detected.
+    misses: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheContents {
+    /// The settings used for the detection, the numbers are only valid for the same ones.
+    fingerprint: String,
+    /// Tracking numbers by commit id.
+    commits: BTreeMap<String, Vec<u32>>,
+}
+
+/// Possible errors while walking the history.
+#[derive(Debug)]
+pub enum HistoryError {
+    /// The revision could not be resolved to a commit.
+    Revision {
+        revision: String,
+        source: git2::Error,
+    },
+    /// The revision range could not be resolved.
+    Range {
+        range: String,
+        source: git2::Error,
+    },
+    /// The commits of the range could not be listed.
+    Walk(git2::Error),
+    Detect(ExtractNumberError),
+    /// The cache could not be written.
+    Cache {
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+/// A single commit, given like on the git command line, e.g. `HEAD`.
+pub fn commit<'r>(repo: &'r Repository, revision: &str) -> Result<Commit<'r>, HistoryError> {
+    repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|source| HistoryError::Revision {
+            revision: revision.to_owned(),
+            source,
+        })
+}
+
+/// The commit at the end of a range, e.g. `HEAD` for `v1.0..HEAD`.
+pub fn range_end<'r>(repo: &'r Repository, range: &str) -> Result<Commit<'r>, HistoryError> {
+    let range_error = |source| HistoryError::Range {
+        range: range.to_owned(),
+        source,
+    };
+    let spec = repo.revparse(range).map_err(range_error)?;
+    let end = if spec.mode().contains(RevparseMode::SINGLE) {
+        spec.from()
+    } else {
+        spec.to()
+    };
+    end.ok_or_else(|| git2::Error::from_str("incomplete range"))
+        .and_then(|object| object.peel_to_commit())
+        .map_err(range_error)
+}
+
+/// All commits in a range, newest first, like `git log`.
+///
+/// The range is given like on the git command line, e.g. `v1.0..HEAD`, `main...feature` or `HEAD`.
+/// Merge commits are skipped, their changes are attributed to the merged commits.
+pub fn commits<'r>(repo: &'r Repository, range: &str) -> Result<Vec<Commit<'r>>, HistoryError> {
+    let range_error = |source| HistoryError::Range {
+        range: range.to_owned(),
+        source,
+    };
+    let spec = repo.revparse(range).map_err(range_error)?;
+    let commit_id = |object: Option<&git2::Object>| {
+        object
+            .ok_or_else(|| git2::Error::from_str("incomplete range"))
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id())
+            .map_err(range_error)
+    };
+
+    let mut walk = repo.revwalk().map_err(HistoryError::Walk)?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+        .map_err(HistoryError::Walk)?;
+    if spec.mode().contains(RevparseMode::SINGLE) {
+        walk.push(commit_id(spec.from())?)
+            .map_err(HistoryError::Walk)?;
+    } else {
+        let from = commit_id(spec.from())?;
+        let to = commit_id(spec.to())?;
+        walk.push(to).map_err(HistoryError::Walk)?;
+        if spec.mode().contains(RevparseMode::MERGE_BASE) {
+            walk.push(from).map_err(HistoryError::Walk)?;
+            let base = repo.merge_base(from, to).map_err(range_error)?;
+            walk.hide(base).map_err(HistoryError::Walk)?;
+        } else {
+            walk.hide(from).map_err(HistoryError::Walk)?;
+        }
+    }
+
+    let mut commits = Vec::new();
+    for id in walk {
+        let commit = id
+            .and_then(|id| repo.find_commit(id))
+            .map_err(HistoryError::Walk)?;
+        if commit.parent_count() <= 1 {
+            commits.push(commit);
+        }
+    }
+    Ok(commits)
+}
+
+/// The commits in `range` that changed any of the `numbers`, or any test case if `numbers` is empty.
+pub fn history(
+    repo: &Repository,
+    range: &str,
+    numbers: &[u32],
+    opts: &Options,
+    cache: &mut NumberCache,
+) -> Result<Vec<HistoryEntry>, HistoryError> {
+    let mut entries = Vec::new();
+    for commit in commits(repo, range)? {
+        let changed = cache.numbers(repo, &commit, opts)?;
+        let matches = if numbers.is_empty() {
+            !changed.is_empty()
+        } else {
+            changed.iter().any(|number| numbers.contains(number))
+        };
+        if !matches {
+            continue;
+        }
+
+        let author = commit.author();
+        entries.push(HistoryEntry {
+            id: commit.id(),
+            summary: commit.summary().unwrap_or_default().to_owned(),
+            author: author.name().unwrap_or_default().to_owned(),
+            time: commit.time(),
+            numbers: changed,
+        });
+    }
+    Ok(entries)
+}
+
+/// The newest commit in `range` that changed each tracking number.
+pub fn last_changes(
+    repo: &Repository,
+    range: &str,
+    opts: &Options,
+    cache: &mut NumberCache,
+) -> Result<BTreeMap<u32, LastChange>, HistoryError> {
+    // Commits are listed newest first, so the first one seen is the last change
+    let mut changes = BTreeMap::new();
+    for commit in commits(repo, range)? {
+        for number in cache.numbers(repo, &commit, opts)? {
+            changes.entry(number).or_insert(LastChange {
+                commit: commit.id(),
+                time: commit.time(),
+            });
+        }
+    }
+    Ok(changes)
+}
+
+impl NumberCache {
+    /// Load the cache of the repository, or start with an empty one.
+    ///
+    /// A cache that can't be read or was created with other settings is discarded.
+    pub fn load(repo: &Repository, opts: &Options) -> Self {
+        let path = repo.path().join(CACHE_FILE);
+        let fingerprint = fingerprint(opts);
+        let contents = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<CacheContents>(&data).ok())
+            .filter(|contents| contents.fingerprint == fingerprint)
+            .unwrap_or_else(|| CacheContents {
+                fingerprint,
+                commits: BTreeMap::new(),
+            });
+
+        Self {
+            path: Some(path),
+            contents,
+            modified: false,
+            misses: 0,
+        }
+    }
+
+    /// A cache that is never stored.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            contents: CacheContents::default(),
+            modified: false,
+            misses: 0,
+        }
+    }
+
+    /// The tracking numbers changed by a commit, detected if they are not cached yet.
+    ///
+    /// `opts` must be the settings the cache was loaded with.
+    pub fn numbers(
+        &mut self,
+        repo: &Repository,
+        commit: &Commit,
+        opts: &Options,
+    ) -> Result<Vec<u32>, HistoryError> {
+        let key = commit.id().to_string();
+        if let Some(numbers) = self.contents.commits.get(&key) {
+            return Ok(numbers.clone());
+        }
+
+        let numbers = changed_tests_in_commit(repo, commit, opts)
+            .map_err(HistoryError::Detect)?
+            .numbers;
+        self.contents.commits.insert(key, numbers.clone());
+        self.modified = true;
+        self.misses += 1;
+        Ok(numbers)
+    }
+
+    /// Number of commits that were not cached yet, so their changes had to be detected.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Store the cache if new commits were added.
+    pub fn save(&mut self) -> Result<(), HistoryError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.modified {
+            return Ok(());
+        }
+
+        let data = serde_json::to_vec(&self.contents).expect("the cache is always serializable");
+        fs::write(path, data).map_err(|source| HistoryError::Cache {
+            path: path.clone(),
+            source,
+        })?;
+        self.modified = false;
+        Ok(())
+    }
+}
+
+/// Everything that affects which numbers are detected for a commit.
+fn fingerprint(opts: &Options) -> String {
+    let formats: Vec<_> = opts.formats.iter().map(|f| f.name()).collect();
+    format!(
+        "{} {:?}",
+        env!("CARGO_PKG_VERSION"),
+        (
+            &opts.test_prefix,
+            &opts.include,
+            &opts.exclude,
+            &opts.step_definitions,
+            &opts.fixtures,
+            opts.fixture_references,
+            opts.language(),
+            formats,
+        )
+    )
+}
+
+/// The date of a commit as `YYYY-MM-DD`, in the time zone of the author.
+pub fn format_date(time: git2::Time) -> String {
+    let seconds = time.seconds() + i64::from(time.offset_minutes()) * 60;
+    let days = seconds.div_euclid(86_400);
+
+    // Convert days since 1970-01-01 into a date of the proleptic Gregorian calendar,
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+impl Display for HistoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HistoryError::Revision { revision, source } => {
+                write!(f, "Invalid revision '{revision}': {source}")
+            }
+            HistoryError::Range { range, source } => {
+                write!(f, "Invalid revision range '{range}': {source}")
+            }
+            HistoryError::Walk(err) => write!(f, "Failed to list the commits: {err}"),
+            HistoryError::Detect(err) => err.fmt(f),
+            HistoryError::Cache { path, source } => {
+                write!(f, "Failed to write the cache {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for HistoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HistoryError::Revision { source, .. } => Some(source),
+            HistoryError::Range { source, .. } => Some(source),
+            HistoryError::Walk(err) => Some(err),
+            HistoryError::Detect(err) => Some(err),
+            HistoryError::Cache { source, .. } => Some(source),
+        }
+    }
+}
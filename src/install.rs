@@ -0,0 +1,312 @@
+//! Installation of the `prepare-commit-msg` git hook.
+//!
+//! The hook is a shell script calling `show-changed-tests`.
+//! Alternatively a `post-commit` hook stores the changed tests in git notes,
+//! see [`crate::notes`].
+//! A hook that already exists is renamed and called by the script first,
+//! so other tools using the same hook keep working.
+
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use git2::{ErrorCode, Repository};
+
+/// Name of the hook `show-changed-tests` runs as.
+pub const HOOK_NAME: &str = "prepare-commit-msg";
+
+/// Name of the hook that stores the changed tests in git notes.
+pub const NOTES_HOOK_NAME: &str = "post-commit";
+
+/// Suffix of a previously installed hook, which is called before `show-changed-tests`.
+pub const CHAINED_SUFFIX: &str = ".chained";
+
+/// Line identifying a hook script written by [`Hook::install`].
+const MARKER: &str = "# Installed by show-changed-tests";
+
+/// The `prepare-commit-msg` or `post-commit` hook of a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hook {
+    path: PathBuf,
+    kind: HookKind,
+}
+
+/// What the hook does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HookKind {
+    /// Add the trailer to the commit message, before the commit is created.
+    #[default]
+    Trailer,
+    /// Attach a git note to the commit, after it was created.
+    Notes,
+}
+
+/// State of the hook file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStatus {
+    /// There is no hook.
+    Missing,
+    /// The hook was installed by `show-changed-tests`.
+    Installed {
+        /// Whether a previously existing hook is called first.
+        chained: bool,
+    },
+    /// The hook belongs to another tool.
+    Foreign,
+}
+
+/// What [`Hook::uninstall`] did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Removal {
+    /// There was no hook to remove.
+    NotInstalled,
+    /// The hook script was removed.
+    Removed {
+        /// Whether the previously existing hook was restored.
+        restored: bool,
+    },
+}
+
+/// Errors while installing or removing the hook.
+#[derive(Debug)]
+pub enum InstallError {
+    /// The hooks directory could not be determined.
+    Git(git2::Error),
+    /// A hook file could not be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// The hook belongs to another tool and is not removed.
+    ForeignHook(PathBuf),
+    /// The existing hook can't be chained, because the name for it is already taken.
+    ChainedHookExists(PathBuf),
+}
+
+impl Display for InstallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallError::Git(err) => write!(f, "Failed to locate the hooks directory: {err}"),
+            InstallError::Io { path, source } => {
+                write!(f, "Failed to access {}: {source}", path.display())
+            }
+            InstallError::ForeignHook(path) => write!(
+                f,
+                "The hook {} was not installed by show-changed-tests",
+                path.display()
+            ),
+            InstallError::ChainedHookExists(path) => write!(
+                f,
+                "Can't keep the existing hook, because {} already exists",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InstallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InstallError::Git(err) => Some(err),
+            InstallError::Io { source, .. } => Some(source),
+            InstallError::ForeignHook(_) | InstallError::ChainedHookExists(_) => None,
+        }
+    }
+}
+
+impl From<git2::Error> for InstallError {
+    fn from(value: git2::Error) -> Self {
+        InstallError::Git(value)
+    }
+}
+
+impl Hook {
+    /// Find the hook of a repository, like git does.
+    ///
+    /// `core.hooksPath` is used if set, relative to the root of the working tree.
+    /// Otherwise the hooks directory of the main repository is used,
+    /// which is shared by all worktrees.
+    pub fn locate(repo: &Repository) -> Result<Self, InstallError> {
+        Self::locate_kind(repo, HookKind::Trailer)
+    }
+
+    /// Find the hook for a kind, see [`Hook::locate`].
+    pub fn locate_kind(repo: &Repository, kind: HookKind) -> Result<Self, InstallError> {
+        let base = repo.workdir().unwrap_or(repo.path());
+        let hooks_dir = match repo.config()?.get_path("core.hooksPath") {
+            Ok(path) => base.join(path),
+            Err(err) if err.code() == ErrorCode::NotFound => common_dir(repo)?.join("hooks"),
+            Err(err) => return Err(err.into()),
+        };
+
+        let name = match kind {
+            HookKind::Trailer => HOOK_NAME,
+            HookKind::Notes => NOTES_HOOK_NAME,
+        };
+        Ok(Self {
+            path: hooks_dir.join(name),
+            kind,
+        })
+    }
+
+    /// Path of the hook script.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the previously existing hook, called before `show-changed-tests`.
+    pub fn chained_path(&self) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(CHAINED_SUFFIX);
+        name.into()
+    }
+
+    /// Check whether the hook is installed.
+    pub fn status(&self) -> Result<HookStatus, InstallError> {
+        let script = match fs::read(&self.path) {
+            Ok(script) => script,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HookStatus::Missing),
+            Err(source) => {
+                return Err(InstallError::Io {
+                    path: self.path.clone(),
+                    source,
+                })
+            }
+        };
+
+        if String::from_utf8_lossy(&script).contains(MARKER) {
+            Ok(HookStatus::Installed {
+                chained: self.chained_path().exists(),
+            })
+        } else {
+            Ok(HookStatus::Foreign)
+        }
+    }
+
+    /// Write the hook script and return the previous status.
+    ///
+    /// An existing hook of another tool is renamed to [`Hook::chained_path`].
+    /// Installing an already installed hook only updates the script.
+    pub fn install(&self) -> Result<HookStatus, InstallError> {
+        let status = self.status()?;
+        if status == HookStatus::Foreign {
+            let chained = self.chained_path();
+            if chained.exists() {
+                return Err(InstallError::ChainedHookExists(chained));
+            }
+            fs::rename(&self.path, &chained).map_err(|source| InstallError::Io {
+                path: self.path.clone(),
+                source,
+            })?;
+        }
+
+        let io_error = |source| InstallError::Io {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        fs::write(&self.path, script(self.kind)).map_err(io_error)?;
+        make_executable(&self.path).map_err(io_error)?;
+
+        Ok(status)
+    }
+
+    /// Remove the hook script and restore the chained hook.
+    ///
+    /// Fails if the hook belongs to another tool.
+    pub fn uninstall(&self) -> Result<Removal, InstallError> {
+        let chained = match self.status()? {
+            HookStatus::Missing => return Ok(Removal::NotInstalled),
+            HookStatus::Foreign => return Err(InstallError::ForeignHook(self.path.clone())),
+            HookStatus::Installed { chained } => chained,
+        };
+
+        fs::remove_file(&self.path).map_err(|source| InstallError::Io {
+            path: self.path.clone(),
+            source,
+        })?;
+        if chained {
+            let chained = self.chained_path();
+            fs::rename(&chained, &self.path).map_err(|source| InstallError::Io {
+                path: chained,
+                source,
+            })?;
+        }
+
+        Ok(Removal::Removed { restored: chained })
+    }
+}
+
+/// The hook script, passing all arguments of git to `show-changed-tests`.
+fn script(kind: HookKind) -> String {
+    let (command, uninstall) = match kind {
+        HookKind::Trailer => (
+            "show-changed-tests -- \"$@\"",
+            "show-changed-tests uninstall",
+        ),
+        // `post-commit` has no arguments, the notes are added to `HEAD`
+        HookKind::Notes => (
+            "show-changed-tests notes",
+            "show-changed-tests uninstall --notes",
+        ),
+    };
+    format!(
+        r#"#!/bin/sh
+{MARKER}, remove it with `{uninstall}`.
+chained="$0{CHAINED_SUFFIX}"
+if [ -x "$chained" ]; then
+    "$chained" "$@" || exit $?
+fi
+exec {command}
+"#
+    )
+}
+
+/// Directory shared by all worktrees of a repository.
+///
+/// For a linked worktree, the `commondir` file in its git directory points to it.
+fn common_dir(repo: &Repository) -> Result<PathBuf, InstallError> {
+    if !repo.is_worktree() {
+        return Ok(repo.path().to_owned());
+    }
+
+    let file = repo.path().join("commondir");
+    let common_dir =
+        fs::read_to_string(&file).map_err(|source| InstallError::Io { path: file, source })?;
+    Ok(without_parent_dirs(
+        &repo.path().join(common_dir.trim_end()),
+    ))
+}
+
+/// Remove the `..` and `.` parts of a path, e.g. of `.git/worktrees/wt/../..`.
+///
+/// Unlike [`fs::canonicalize`], symlinks are kept, so the path is displayed as the user knows it.
+fn without_parent_dirs(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(result.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                result.pop();
+            }
+            _ => result.push(component),
+        }
+    }
+    result
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+// Git for Windows runs hooks via its shell, regardless of permissions
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -1,122 +1,468 @@
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::Display,
-    fs::File,
-    io::{self, Read},
     ops::Range,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
-use gherkin::{Feature, Span};
-use git2::{Diff, DiffOptions, Repository};
+use git2::{Commit, Diff, DiffOptions, Repository, Tree};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+
+pub mod assign;
+pub mod audit;
+pub mod catalog;
+pub mod config;
+pub mod doctor;
+pub mod export;
+mod fixtures;
+pub mod formats;
+pub mod history;
+pub mod install;
+pub mod lint;
+pub mod notes;
+#[cfg(feature = "notify")]
+pub mod notify;
+pub mod report;
+pub mod scan;
+pub mod steps;
+
+use formats::{ParseError, TestFormat, TestItem};
 
 /// User configuration to affect the behaviour.
 pub struct Options {
     /// Prefix used in tags to link the test case to an item.
     pub test_prefix: String,
+    /// Glob patterns of the files that contain test cases.
+    pub include: Vec<String>,
+    /// Glob patterns of files to skip, even if they match `include`.
+    pub exclude: Vec<String>,
+    /// Glob patterns of files with step definitions.
+    ///
+    /// If a step definition in one of these files changes,
+    /// all scenarios using the step are reported as changed.
+    /// Empty to disable the analysis.
+    pub step_definitions: Vec<String>,
+    /// Files used by test cases, as glob pattern and the tracking numbers of the test cases.
+    pub fixtures: BTreeMap<String, Vec<u32>>,
+    /// Whether to detect references to changed files in the steps of scenarios.
+    pub fixture_references: bool,
+    /// Formats to extract the test cases from the included files.
+    ///
+    /// Each file is handled by the first format that accepts its path.
+    /// The Gherkin format also determines [`Options::language`].
+    pub formats: Vec<Box<dyn TestFormat>>,
+}
+
+/// What to do with problematic test cases,
+/// e.g. changed scenarios without tracking number or numbers rejected by the catalog.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    /// Silently skip the test cases.
+    #[default]
+    Ignore,
+    /// Print the test cases to stderr, but continue normally.
+    Warn,
+    /// Print the test cases to stderr and exit with an error.
+    Fail,
+}
+
+/// All test changes detected in the staged changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangedTests {
+    /// Tracking numbers of the changed scenarios, sorted and without duplicates.
+    pub numbers: Vec<u32>,
+    /// Changed scenarios that do not have a tracking number.
+    pub untagged: Vec<UntaggedScenario>,
+    /// Every changed test case together with the reason, sorted by number.
+    pub tests: Vec<ChangedTest>,
+    /// Problems that did not stop the detection, but may cause missing tests.
+    pub warnings: Vec<Warning>,
+}
+
+/// A test case affected by the staged changes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChangedTest {
+    /// Tracking number, `None` if the test case does not have one.
+    pub number: Option<u32>,
+    /// The test case, `None` if it is only known by its number, e.g. via [`Options::fixtures`].
+    pub test: Option<TestCase>,
+    /// Why the test case is affected.
+    pub kind: ChangeKind,
+    /// The changed lines that affect the test case, empty if unknown.
+    pub lines: Vec<ChangedLine>,
+}
+
+/// A test case in a file.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TestCase {
+    /// Path of the test file, relative to the repository root.
+    pub path: PathBuf,
+    /// Line where the test case starts, 1 based.
+    pub line: usize,
+    /// Name of the test case.
+    pub name: String,
+}
+
+/// A changed scenario without a tracking number.
+pub type UntaggedScenario = TestCase;
+
+/// The reason a test case is reported as changed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    /// The test case itself changed.
+    TestCase,
+    /// A section shared by multiple test cases changed, like a background.
+    Shared { name: String },
+    /// A step definition used by the test case changed.
+    StepDefinition { function: String },
+    /// A file used by the test case changed.
+    Fixture { path: PathBuf },
+}
+
+/// A single added or removed line of the staged changes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChangedLine {
+    /// Path of the changed file, relative to the repository root.
+    pub path: PathBuf,
+    /// Line number where the change happened, 1 based
+    pub line: u32,
+    /// Whether to check the previous or changed version of the file.
+    /// E.g. pure deletions should be checked in the `Old` version,
+    /// pure additions in the `New`.
+    pub version: Version,
+    /// Content of the line, without the line break.
+    pub text: String,
+}
+
+/// A version of a changed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Version {
+    /// The version in `HEAD` or the parent commit, the line was removed.
+    Old,
+    /// The staged version or the commit, the line was added.
+    New,
+}
+
+/// A changed test file that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileParseError {
+    /// Path of the file, relative to the repository root.
+    pub path: PathBuf,
+    /// Whether the version in `HEAD` or the staged version failed to parse.
+    pub version: Version,
+    /// Name of the format used to parse the file.
+    pub format: String,
+    pub error: ParseError,
+}
+
+/// A problem that did not stop the detection, but may cause missing tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A test file could not be parsed and was skipped.
+    Parse(FileParseError),
+    /// The pattern of a changed step definition is not a valid regular expression,
+    /// so the scenarios using it are unknown.
+    InvalidStepPattern {
+        path: PathBuf,
+        function: String,
+        message: String,
+    },
 }
 
 /// Possible errors that can happen when trying to figure out the changed tests.
 #[derive(Debug)]
 pub enum ExtractNumberError {
-    GitError,
-    Io(io::Error),
+    /// A git operation failed.
+    Git {
+        operation: Operation,
+        /// The file the operation was about, if any.
+        path: Option<PathBuf>,
+        source: git2::Error,
+    },
+    /// A configured glob pattern is invalid.
+    InvalidPattern(globset::Error),
+}
+
+/// The git operations that can fail while detecting the changed tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// Find the commit and tree of `HEAD`.
+    ResolveHead,
+    /// Compare `HEAD` with the index, or a commit with its parent.
+    Diff,
+    /// Read the index.
+    ReadIndex,
+    /// Read the contents of a file from the object database.
+    ReadBlob,
+    /// Find the commit given on the command line.
+    ResolveRevision,
+    /// List the files of a commit.
+    ReadTree,
+}
+
+impl Warning {
+    /// Whether both warnings are about the same problem.
+    ///
+    /// The messages of the Gherkin parser are not stable, so parse errors are compared by file.
+    fn is_same_problem(&self, other: &Warning) -> bool {
+        match (self, other) {
+            (Warning::Parse(a), Warning::Parse(b)) => a.path == b.path && a.version == b.version,
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl ExtractNumberError {
+    /// Wrap a failed git operation, for use with `map_err`.
+    fn git(operation: Operation, path: Option<&Path>) -> impl FnOnce(git2::Error) -> Self + '_ {
+        move |source| Self::Git {
+            operation,
+            path: path.map(ToOwned::to_owned),
+            source,
+        }
+    }
 }
 
 pub fn changed_test_numbers(
     repo: &Repository,
     opts: &Options,
 ) -> Result<Vec<u32>, ExtractNumberError> {
-    let mut diff_opts = DiffOptions::default();
-    diff_opts.patience(true).context_lines(0);
+    Ok(changed_tests(repo, opts)?.numbers)
+}
 
+/// Detect the changed scenarios, both with and without a tracking number.
+pub fn changed_tests(
+    repo: &Repository,
+    opts: &Options,
+) -> Result<ChangedTests, ExtractNumberError> {
     let head = repo
         .resolve_reference_from_short_name("HEAD")
-        .map_err(|_| ExtractNumberError::GitError)?
-        .peel_to_commit()
-        .map_err(|_| ExtractNumberError::GitError)?;
-    let tree = head.tree().unwrap();
+        .and_then(|head| head.peel_to_commit())
+        .map_err(ExtractNumberError::git(Operation::ResolveHead, None))?;
+    let tree = head
+        .tree()
+        .map_err(ExtractNumberError::git(Operation::ResolveHead, None))?;
+
+    detect_changes(
+        repo,
+        &Versions {
+            old: Some(tree),
+            new: None,
+        },
+        opts,
+    )
+}
 
-    let diff = repo
-        .diff_tree_to_index(Some(&tree), None, Some(&mut diff_opts))
-        .map_err(|_| ExtractNumberError::GitError)?;
+/// Detect the test cases changed by a commit, compared with its first parent.
+///
+/// Works like [`changed_tests`] with the commit as the staged version,
+/// e.g. to find the numbers of commits in the history.
+pub fn changed_tests_in_commit(
+    repo: &Repository,
+    commit: &Commit,
+    opts: &Options,
+) -> Result<ChangedTests, ExtractNumberError> {
+    let old = match commit.parent(0) {
+        Ok(parent) => Some(
+            parent
+                .tree()
+                .map_err(ExtractNumberError::git(Operation::ReadTree, None))?,
+        ),
+        // The first commit adds all of its files
+        Err(_) => None,
+    };
+    let new = commit
+        .tree()
+        .map_err(ExtractNumberError::git(Operation::ReadTree, None))?;
+
+    detect_changes(
+        repo,
+        &Versions {
+            old,
+            new: Some(new),
+        },
+        opts,
+    )
+}
 
-    let changes = changes_in_tests(diff);
+/// The two versions of the files that are compared.
+pub(crate) struct Versions<'r> {
+    /// The version before the changes, `None` if there is none, like for the first commit.
+    old: Option<Tree<'r>>,
+    /// The changed version, `None` for the staged files.
+    new: Option<Tree<'r>>,
+}
 
-    let mut numbers = Vec::new();
+fn detect_changes(
+    repo: &Repository,
+    versions: &Versions,
+    opts: &Options,
+) -> Result<ChangedTests, ExtractNumberError> {
+    let mut diff_opts = DiffOptions::default();
+    diff_opts.patience(true).context_lines(0);
+
+    let diff = match &versions.new {
+        Some(new) => repo.diff_tree_to_tree(versions.old.as_ref(), Some(new), Some(&mut diff_opts)),
+        None => repo.diff_tree_to_index(versions.old.as_ref(), None, Some(&mut diff_opts)),
+    }
+    .map_err(ExtractNumberError::git(Operation::Diff, None))?;
+
+    let filter = PathFilter::new(opts)?;
+    let changes = changed_lines(&diff, &filter);
+
+    let mut tests = Vec::new();
+    let mut warnings = Vec::new();
 
     for change in &changes {
-        let text = if change.version == Version::Old {
-            let blob = tree
-                .get_path(&change.path)
-                .map_err(|_| ExtractNumberError::GitError)?
-                .to_object(repo)
-                .map_err(|_| ExtractNumberError::GitError)?
-                .peel_to_blob()
-                .map_err(|_| ExtractNumberError::GitError)?;
-
-            let text = String::from_utf8_lossy(blob.content());
-            text.to_string()
-        } else {
-            let full_path = repo.path().parent().unwrap().join(&change.path);
-
-            let mut text = String::new();
-            File::open(&full_path)?.read_to_string(&mut text)?;
-
-            text
-        };
+        let failed_before = warnings.iter().any(|w| match w {
+            Warning::Parse(e) => e.path == change.path && e.version == change.version,
+            _ => false,
+        });
+        if failed_before {
+            continue;
+        }
+
+        let text = read_version(repo, versions, change)?;
 
-        let Ok(feature) = Feature::parse(&text, Default::default()) else {
-            eprintln!("Failed to parse gherkin file {}", change.path.display());
+        let Some(format) = opts.formats.iter().find(|f| f.handles(&change.path)) else {
             continue;
         };
 
+        let file = match format.parse(&text) {
+            Ok(file) => file,
+            Err(error) => {
+                warnings.push(Warning::Parse(FileParseError {
+                    path: change.path.clone(),
+                    version: change.version,
+                    format: format.name().to_owned(),
+                    error,
+                }));
+                continue;
+            }
+        };
+
         let offsets = calculate_line_spans(&text);
-        let changed_line = line_to_byte_offset(offsets.clone(), change.line);
+        let Some(changed_line) = line_to_byte_offset(&offsets, change.line) else {
+            continue;
+        };
 
-        // Check scenarios
-        let scenario = feature
-            .scenarios
+        let test = |item: &TestItem, kind| ChangedTest {
+            number: item.number(&opts.test_prefix),
+            test: Some(TestCase::new(&change.path, item)),
+            kind,
+            lines: vec![change.clone()],
+        };
+
+        // Check test cases. A span can end with the indentation of the next keyword,
+        // so on such a line the later test case is the one that starts there.
+        if let Some(item) = file
+            .items
             .iter()
-            .find(|s| s.span.intersects(&changed_line));
-        if let Some(scenario) = scenario {
-            let testcase = scenario
-                .tags
-                .iter()
-                .find_map(|tag| parse_testcase_number(tag, &opts.test_prefix));
-            if let Some(num) = testcase {
-                numbers.push(num);
-            }
+            .rfind(|i| i.span.intersects(&changed_line))
+        {
+            tests.push(test(item, ChangeKind::TestCase));
         }
 
-        // Check background
-        if let Some(background) = feature.background {
-            if background.span.intersects(&changed_line) {
-                numbers.extend(feature.scenarios.iter().filter_map(|s| {
-                    s.tags
-                        .iter()
-                        .find_map(|tag| parse_testcase_number(tag, &opts.test_prefix))
+        // Check sections shared by multiple test cases, e.g. backgrounds
+        for shared in &file.shared {
+            if shared.span.intersects(&changed_line) {
+                tests.extend(shared.items.iter().map(|&idx| {
+                    let kind = ChangeKind::Shared {
+                        name: shared.name.clone(),
+                    };
+                    test(&file.items[idx], kind)
                 }));
             }
         }
     }
 
-    // collect into hashset and back into vec to get rid of duplicates
-    // This also sorts the numbers
-    numbers = numbers
-        .into_iter()
-        .collect::<BTreeSet<_>>()
-        .into_iter()
+    tests.extend(steps::changed_step_usages(
+        repo,
+        versions,
+        &diff,
+        opts,
+        &mut warnings,
+    )?);
+    tests.extend(fixtures::changed_fixture_usages(
+        repo,
+        versions,
+        &diff,
+        opts,
+        &mut warnings,
+    )?);
+
+    // Files are parsed for multiple purposes, but each problem is reported once
+    let mut unique_warnings: Vec<Warning> = Vec::new();
+    for warning in warnings {
+        if !unique_warnings.iter().any(|w| w.is_same_problem(&warning)) {
+            unique_warnings.push(warning);
+        }
+    }
+
+    let tests = merge_changed_tests(tests);
+
+    // collect into a set to get rid of duplicates, this also sorts them
+    let numbers: BTreeSet<_> = tests.iter().filter_map(|test| test.number).collect();
+    let untagged: BTreeSet<_> = tests
+        .iter()
+        .filter(|test| test.number.is_none())
+        .filter_map(|test| test.test.clone())
         .collect();
 
-    Ok(numbers)
+    Ok(ChangedTests {
+        numbers: numbers.into_iter().collect(),
+        untagged: untagged.into_iter().collect(),
+        tests,
+        warnings: unique_warnings,
+    })
+}
+
+/// Combine the entries for the same test case and reason, and sort them.
+fn merge_changed_tests(tests: Vec<ChangedTest>) -> Vec<ChangedTest> {
+    let mut merged: BTreeMap<_, BTreeSet<ChangedLine>> = BTreeMap::new();
+    for test in tests {
+        merged
+            .entry((test.number, test.test, test.kind))
+            .or_default()
+            .extend(test.lines);
+    }
+
+    merged
+        .into_iter()
+        .map(|((number, test, kind), lines)| ChangedTest {
+            number,
+            test,
+            kind,
+            lines: lines.into_iter().collect(),
+        })
+        .collect()
+}
+
+/// The changed test for a Gherkin scenario.
+fn changed_scenario(
+    path: &Path,
+    scenario: &gherkin::Scenario,
+    opts: &Options,
+    kind: ChangeKind,
+    lines: Vec<ChangedLine>,
+) -> ChangedTest {
+    ChangedTest {
+        number: formats::tracking_number(&scenario.tags, &opts.test_prefix),
+        test: Some(TestCase {
+            path: path.to_owned(),
+            line: scenario.position.line,
+            name: scenario.name.clone(),
+        }),
+        kind,
+        lines,
+    }
 }
 
 pub fn format_issue_references(numbers: &[u32], width: usize, prefix: &str) -> String {
     let mut lines = Vec::new();
 
-    assert!(prefix.len() < width);
-
     let delimiter = ", ";
 
     let mut print_delimiter = false;
@@ -126,7 +472,8 @@ pub fn format_issue_references(numbers: &[u32], width: usize, prefix: &str) -> S
 
         let extra_width = ref_text.len() + if print_delimiter { delimiter.len() } else { 0 };
 
-        if current_line.len() + extra_width > width {
+        // A reference that does not fit on an empty line is kept there anyway
+        if print_delimiter && current_line.len() + extra_width > width {
             lines.push(current_line);
             current_line = prefix.to_owned();
             print_delimiter = false;
@@ -153,6 +500,23 @@ pub fn format_issue_references(numbers: &[u32], width: usize, prefix: &str) -> S
     output
 }
 
+/// The tracking numbers referenced in the trailers of a commit message, sorted and without duplicates.
+///
+/// This is the reverse of [`format_issue_references`]: every line starting with `trailer:`,
+/// ignoring case like git, is searched for references like `#123`.
+pub fn parse_issue_references(message: &str, trailer: &str) -> Vec<u32> {
+    let numbers: BTreeSet<u32> = message
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(trailer).then_some(value)
+        })
+        .flat_map(|value| value.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter_map(|reference| reference.strip_prefix('#')?.parse().ok())
+        .collect();
+    numbers.into_iter().collect()
+}
+
 /// Insert the trailer in the "correct" position of a commit message.
 ///
 /// This is not strictly the end, as the message might contain instructions from git,
@@ -178,28 +542,130 @@ pub fn extend_message(message: &str, trailer: &str) -> String {
     new_contents
 }
 
-#[derive(Debug, Clone)]
-struct Change {
-    /// Line number where the change happened, 1 based
-    pub line: u32,
-    pub path: PathBuf,
-    /// Whether to check the previous or changed version of the file.
-    /// E.g. pure deletions should be checked in the `Old` version,
-    /// pure additions in the `New`.
-    pub version: Version,
+/// Read the contents of a file in the version that contains the change.
+fn read_version(
+    repo: &Repository,
+    versions: &Versions,
+    change: &ChangedLine,
+) -> Result<String, ExtractNumberError> {
+    let tree = match change.version {
+        Version::Old => versions.old.as_ref(),
+        Version::New => versions.new.as_ref(),
+    };
+
+    let blob = if let Some(tree) = tree {
+        tree.get_path(&change.path)
+            .and_then(|entry| entry.to_object(repo))
+            .and_then(|object| object.peel_to_blob())
+    } else if change.version == Version::Old {
+        Err(git2::Error::from_str("there is no previous version"))
+    } else {
+        // The diff is against the index, so the line numbers refer to the staged version,
+        // which can differ from the working directory
+        repo.index().and_then(|index| {
+            index
+                .get_path(&change.path, 0)
+                .ok_or_else(|| git2::Error::from_str("the file is not in the index"))
+                .and_then(|entry| repo.find_blob(entry.id))
+        })
+    }
+    .map_err(ExtractNumberError::git(
+        Operation::ReadBlob,
+        Some(&change.path),
+    ))?;
 
-    // Newly added line, for debugging.
-    #[allow(dead_code)]
-    pub text: String,
+    Ok(String::from_utf8_lossy(blob.content()).into_owned())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Version {
-    Old,
-    New,
+/// All files of the changed version that contain Gherkin features.
+fn new_features(
+    repo: &Repository,
+    versions: &Versions,
+    opts: &Options,
+) -> Result<Vec<(PathBuf, String)>, ExtractNumberError> {
+    let filter = PathFilter::new(opts)?;
+    let files = match &versions.new {
+        Some(tree) => scan::tree_files(tree)?,
+        None => scan::index_files(repo)?,
+    };
+
+    let mut features = Vec::new();
+    for (path, id) in files {
+        if !filter.matches(&path) {
+            continue;
+        }
+        let is_gherkin = opts
+            .formats
+            .iter()
+            .find(|f| f.handles(&path))
+            .is_some_and(|f| f.as_gherkin().is_some());
+        if !is_gherkin {
+            continue;
+        }
+
+        let blob = repo
+            .find_blob(id)
+            .map_err(ExtractNumberError::git(Operation::ReadBlob, Some(&path)))?;
+        features.push((path, String::from_utf8_lossy(blob.content()).into_owned()));
+    }
+
+    Ok(features)
 }
 
-fn changes_in_tests(diff: Diff) -> Vec<Change> {
+/// Parse a feature of the changed version, or record why it could not be parsed.
+fn parse_new_feature(
+    path: &Path,
+    text: &str,
+    opts: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Option<gherkin::Feature> {
+    match formats::parse_feature(text, opts.language()) {
+        Ok(feature) => Some(feature),
+        Err(error) => {
+            warnings.push(Warning::Parse(FileParseError {
+                path: path.to_owned(),
+                version: Version::New,
+                format: formats::Gherkin::default().name().to_owned(),
+                error,
+            }));
+            None
+        }
+    }
+}
+
+/// Decides which files are checked for test cases.
+struct PathFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    fn new(opts: &Options) -> Result<Self, globset::Error> {
+        Self::from_patterns(&opts.include, &opts.exclude)
+    }
+
+    fn from_patterns(include: &[String], exclude: &[String]) -> Result<Self, globset::Error> {
+        Ok(Self {
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+        })
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        self.include.is_match(path) && !self.exclude.is_match(path)
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+    }
+    builder.build()
+}
+
+/// Added and removed lines of the files matching the filter.
+fn changed_lines(diff: &Diff, filter: &PathFilter) -> Vec<ChangedLine> {
     let mut result = Vec::new();
 
     let _ = diff.foreach(
@@ -207,45 +673,41 @@ fn changes_in_tests(diff: Diff) -> Vec<Change> {
         None,
         None,
         Some(&mut |file, _, line| {
-            if file
-                .new_file()
-                .path()
-                .is_none_or(|p| p.extension().is_none_or(|e| e != "feature"))
+            let old_path = file.old_file().path();
+            let new_path = file.new_file().path();
+
+            // Renamed files are checked if either of the names matches
+            if ![old_path, new_path]
+                .into_iter()
+                .flatten()
+                .any(|p| filter.matches(p))
             {
                 return true;
             }
 
-            let Some(path) = file.new_file().path().map(ToOwned::to_owned) else {
-                return true;
-            };
-
-            let text = String::from_utf8_lossy(line.content()).to_string();
+            let text = String::from_utf8_lossy(line.content())
+                .trim_end_matches(['\r', '\n'])
+                .to_owned();
 
             if text.trim().is_empty() {
                 return true;
             }
 
-            let change = match (line.old_lineno(), line.new_lineno()) {
-                (None, Some(line)) => Change {
-                    line,
-                    path,
-                    version: Version::New,
-                    text,
-                },
-                (Some(_), Some(line)) => Change {
-                    line,
-                    path,
-                    version: Version::New,
-                    text,
-                },
-                (Some(line), None) => Change {
-                    line,
-                    path,
-                    version: Version::Old,
-                    text,
-                },
+            let (line, version, path) = match (line.old_lineno(), line.new_lineno()) {
+                (_, Some(line)) => (line, Version::New, new_path),
+                (Some(line), None) => (line, Version::Old, old_path),
                 (None, None) => return true,
             };
+            let Some(path) = path.map(ToOwned::to_owned) else {
+                return true;
+            };
+
+            let change = ChangedLine {
+                path,
+                line,
+                version,
+                text,
+            };
 
             result.push(change);
 
@@ -257,59 +719,147 @@ fn changes_in_tests(diff: Diff) -> Vec<Change> {
 }
 
 trait SpanExt {
-    fn contains(&self, val: usize) -> bool;
-    fn intersects(&self, other: &Range<usize>) -> bool {
-        self.contains(other.start) || self.contains(other.end)
-    }
+    fn intersects(&self, other: &Range<usize>) -> bool;
 }
 
-impl SpanExt for Span {
-    fn contains(&self, val: usize) -> bool {
-        self.start <= val && val < self.end
+impl SpanExt for Range<usize> {
+    fn intersects(&self, other: &Range<usize>) -> bool {
+        self.start < other.end && other.start < self.end
     }
 }
 
 type LineOffsets = Vec<Range<usize>>;
-fn line_to_byte_offset(offsets: LineOffsets, line: u32) -> Range<usize> {
-    offsets[(line - 1) as usize].clone()
+
+/// Byte range of a 1 based line, `None` if the file has fewer lines.
+fn line_to_byte_offset(offsets: &LineOffsets, line: u32) -> Option<Range<usize>> {
+    let idx = usize::try_from(line).ok()?.checked_sub(1)?;
+    offsets.get(idx).cloned()
 }
 
 fn calculate_line_spans(text: &str) -> LineOffsets {
     let mut ptr = 0;
     text.split_inclusive('\n')
         .fold(vec![], |mut offsets, line| {
-            let end = ptr + line.len() + 1;
+            let end = ptr + line.len();
             offsets.push(ptr..end);
             ptr = end;
             offsets
         })
 }
 
-fn parse_testcase_number(tag: &str, prefix: &str) -> Option<u32> {
-    tag.strip_prefix(prefix)?.parse().ok()
+impl Options {
+    /// Language of Gherkin files without `# language:` header, e.g. `de`.
+    ///
+    /// Taken from the Gherkin format in [`Options::formats`],
+    /// [`formats::DEFAULT_LANGUAGE`] if there is none.
+    pub fn language(&self) -> &str {
+        self.formats
+            .iter()
+            .find_map(|format| format.as_gherkin())
+            .map_or(formats::DEFAULT_LANGUAGE, formats::Gherkin::language)
+    }
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             test_prefix: "tc:".into(),
+            include: vec!["**/*.feature".into()],
+            exclude: Vec::new(),
+            step_definitions: Vec::new(),
+            fixtures: BTreeMap::new(),
+            fixture_references: false,
+            formats: formats::default_formats(formats::DEFAULT_LANGUAGE),
         }
     }
 }
 
-impl From<io::Error> for ExtractNumberError {
-    fn from(value: io::Error) -> Self {
-        ExtractNumberError::Io(value)
+impl TestCase {
+    fn new(path: &Path, item: &TestItem) -> Self {
+        Self {
+            path: path.to_owned(),
+            line: item.line,
+            name: item.name.clone(),
+        }
+    }
+}
+
+impl Display for TestCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.name)
+    }
+}
+
+impl Display for FileParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to parse {} file {}: {}",
+            self.format,
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+impl From<globset::Error> for ExtractNumberError {
+    fn from(value: globset::Error) -> Self {
+        ExtractNumberError::InvalidPattern(value)
     }
 }
 
 impl Display for ExtractNumberError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ExtractNumberError::GitError => write!(f, "Failed to interact with git!"),
-            ExtractNumberError::Io(error) => write!(f, "IO Error: {error}"),
+            ExtractNumberError::Git {
+                operation,
+                path: Some(path),
+                source,
+            } => write!(f, "Failed to {operation} of {}: {source}", path.display()),
+            ExtractNumberError::Git {
+                operation, source, ..
+            } => write!(f, "Failed to {operation}: {source}"),
+            ExtractNumberError::InvalidPattern(error) => write!(f, "Invalid file pattern: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ExtractNumberError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractNumberError::Git { source, .. } => Some(source),
+            ExtractNumberError::InvalidPattern(error) => Some(error),
         }
     }
 }
 
-impl std::error::Error for ExtractNumberError {}
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Operation::ResolveHead => "resolve HEAD",
+            Operation::Diff => "compare the versions",
+            Operation::ReadIndex => "read the index",
+            Operation::ReadBlob => "read the contents",
+            Operation::ResolveRevision => "resolve the revision",
+            Operation::ReadTree => "list the files of the revision",
+        };
+        f.write_str(text)
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::Parse(error) => write!(f, "{error}"),
+            Warning::InvalidStepPattern {
+                path,
+                function,
+                message,
+            } => write!(
+                f,
+                "Invalid step pattern of {function} in {}: {message}",
+                path.display()
+            ),
+        }
+    }
+}
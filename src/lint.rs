@@ -0,0 +1,168 @@
+//! Checks of the tracking numbers of all test cases.
+//!
+//! A tag that almost matches the prefix, like `@tc:12a`, is silently ignored by the hook,
+//! and a number used by two scenarios makes the trailer ambiguous.
+//! The lint finds these problems before they end up in a commit.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    catalog::{Catalog, Problem},
+    scan::{ScannedTest, ScannedTests},
+    TestCase, Warning,
+};
+
+/// A problem with the tracking numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// A test file could not be parsed, so its test cases were not checked.
+    Parse(Warning),
+    /// The same number is used by multiple test cases.
+    DuplicateNumber { number: u32, tests: Vec<TestCase> },
+    /// A test case has more than one tracking number, only the first one is used.
+    MultipleNumbers { test: TestCase, numbers: Vec<u32> },
+    /// A tag looks like a tracking number, but does not match the prefix exactly.
+    MalformedTag { test: TestCase, tag: String },
+    /// A tracking number is unknown or retired according to the catalog.
+    Catalog {
+        test: TestCase,
+        number: u32,
+        problem: Problem,
+    },
+}
+
+/// Check all scanned test cases, with tracking numbers in tags starting with `prefix`.
+pub fn lint(scanned: &ScannedTests, prefix: &str) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = scanned.warnings.iter().cloned().map(Issue::Parse).collect();
+
+    let mut by_number: BTreeMap<u32, Vec<TestCase>> = BTreeMap::new();
+    for test in &scanned.tests {
+        for number in numbers(test, prefix) {
+            by_number.entry(number).or_default().push(test.test.clone());
+        }
+    }
+    issues.extend(
+        by_number
+            .into_iter()
+            .filter(|(_, tests)| tests.len() > 1)
+            .map(|(number, tests)| Issue::DuplicateNumber { number, tests }),
+    );
+
+    for test in &scanned.tests {
+        let numbers = numbers(test, prefix);
+        if numbers.len() > 1 {
+            issues.push(Issue::MultipleNumbers {
+                test: test.test.clone(),
+                numbers,
+            });
+        }
+
+        issues.extend(
+            test.tags
+                .iter()
+                .filter(|tag| is_malformed(tag, prefix))
+                .map(|tag| Issue::MalformedTag {
+                    test: test.test.clone(),
+                    tag: tag.clone(),
+                }),
+        );
+    }
+
+    issues
+}
+
+/// Check the tracking numbers of all scanned test cases against a catalog.
+pub fn check_catalog(scanned: &ScannedTests, prefix: &str, catalog: &Catalog) -> Vec<Issue> {
+    scanned
+        .tests
+        .iter()
+        .flat_map(|test| {
+            numbers(test, prefix).into_iter().filter_map(|number| {
+                Some(Issue::Catalog {
+                    test: test.test.clone(),
+                    number,
+                    problem: catalog.check(number)?,
+                })
+            })
+        })
+        .collect()
+}
+
+/// All distinct tracking numbers of a test case, in the order of the tags.
+fn numbers(test: &ScannedTest, prefix: &str) -> Vec<u32> {
+    let mut numbers = Vec::new();
+    for number in test
+        .tags
+        .iter()
+        .filter_map(|tag| tag.strip_prefix(prefix)?.parse().ok())
+    {
+        if !numbers.contains(&number) {
+            numbers.push(number);
+        }
+    }
+    numbers
+}
+
+/// Whether the tag was probably meant to be a tracking number.
+///
+/// That is the case if it starts with the prefix, ignoring case and separators like `:`,
+/// and contains a digit, but is not a valid tracking number, e.g. `tc:12a`, `TC:12` or `tc12`.
+/// Tags that merely start with the same letters, like `tcp2` for the prefix `tc:`, are fine.
+fn is_malformed(tag: &str, prefix: &str) -> bool {
+    if tag
+        .strip_prefix(prefix)
+        .is_some_and(|number| number.parse::<u32>().is_ok())
+    {
+        return false;
+    }
+
+    let stem = prefix.trim_end_matches(|c: char| !c.is_alphanumeric());
+    if stem.is_empty() {
+        return false;
+    }
+    let Some(start) = tag.get(..stem.len()) else {
+        return false;
+    };
+    let rest = &tag[stem.len()..];
+
+    start.eq_ignore_ascii_case(stem)
+        && !rest.starts_with(|c: char| c.is_alphabetic())
+        && rest.contains(|c: char| c.is_ascii_digit())
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::Parse(warning) => write!(f, "{warning}"),
+            Issue::DuplicateNumber { number, tests } => {
+                write!(
+                    f,
+                    "Tracking number {number} is used by multiple test cases:"
+                )?;
+                for test in tests {
+                    write!(f, "\n  {test}")?;
+                }
+                Ok(())
+            }
+            Issue::MultipleNumbers { test, numbers } => {
+                let numbers: Vec<_> = numbers.iter().map(u32::to_string).collect();
+                write!(
+                    f,
+                    "{test}: multiple tracking numbers {}, only the first one is used",
+                    numbers.join(", ")
+                )
+            }
+            Issue::MalformedTag { test, tag } => {
+                write!(
+                    f,
+                    "{test}: tag '{tag}' looks like a malformed tracking number"
+                )
+            }
+            Issue::Catalog {
+                test,
+                number,
+                problem,
+            } => write!(f, "{test}: tracking number {number} {problem}"),
+        }
+    }
+}
@@ -1,33 +1,136 @@
 use std::{
+    error::Error,
+    fmt::Display,
     fs::File,
-    io::{Read, Seek, SeekFrom, Write},
-    path::PathBuf,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
 };
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 use git2::Repository;
-use show_changed_tests::{changed_test_numbers, extend_message, format_issue_references, Options};
+use show_changed_tests::{
+    assign::{assign_numbers, Allocation, AssignError, NumberRange},
+    audit::audit,
+    catalog::{Catalog, CatalogError},
+    changed_tests,
+    config::{Config, ConfigError, Outcome},
+    doctor::{self, Severity},
+    export::changed_in_range,
+    extend_message, format_issue_references,
+    history::{self, history, HistoryError, NumberCache},
+    install::{Hook, HookKind, HookStatus, InstallError, Removal},
+    lint::{check_catalog, lint},
+    notes::{annotate, NoteFormat, NotesError},
+    report::{self, ExportFormat, ListFormat, OutputFormat},
+    scan::{scan, Revision},
+    ExtractNumberError, Options, Policy,
+};
+#[cfg(feature = "notify")]
+use show_changed_tests::{
+    history::last_changes,
+    notify::{notifications, Notifier, NotifyError},
+};
 
-fn main() {
+fn main() -> ExitCode {
     let cli = Cli::parse();
 
-    let repo = Repository::open_from_env().unwrap();
+    if let Some(command) = &cli.command {
+        // Explicitly called commands report their failure
+        return run_command(command).unwrap_or_else(|err| {
+            eprintln!("show-changed-tests: {err}");
+            ExitCode::FAILURE
+        });
+    }
 
-    let numbers = match changed_test_numbers(&repo, &cli.clone().into()) {
-        Ok(num) => num,
+    // Until the configuration is loaded, only the command line is known
+    let mut error_exit_code = Config::from(&cli.config).exit_code(Outcome::Error);
+
+    match run(&cli, &mut error_exit_code) {
+        Ok(code) => code,
         Err(err) => {
-            eprintln!("Failed to detect changed tests!");
-            eprintln!("{err}");
-            return;
+            // A failure of the hook must not block the commit, unless configured otherwise
+            eprintln!("show-changed-tests: {err}");
+            ExitCode::from(error_exit_code)
         }
+    }
+}
+
+fn run(cli: &Cli, error_exit_code: &mut u8) -> Result<ExitCode, HookError> {
+    let repo = Repository::open_from_env().map_err(HookError::OpenRepository)?;
+
+    let config = load_config(&repo, Config::from(&cli.config))?;
+    *error_exit_code = config.exit_code(Outcome::Error);
+
+    let changes = changed_tests(&repo, &config.options()).map_err(HookError::Detect)?;
+    let violations = match load_catalog(&repo, &config)? {
+        Some(catalog) if config.unknown_numbers() != Policy::Ignore => catalog.violations(&changes),
+        _ => Vec::new(),
     };
 
-    let trailer = format_issue_references(&numbers, 72, &format!("{}: ", cli.trailer));
+    // The first outcome with a non-zero exit code decides, ordered by severity
+    let outcomes = [
+        (Outcome::ParseError, !changes.warnings.is_empty()),
+        (Outcome::Untagged, !changes.untagged.is_empty()),
+        (Outcome::UnknownNumbers, !violations.is_empty()),
+        (
+            Outcome::NoChanges,
+            changes.numbers.is_empty() && changes.untagged.is_empty(),
+        ),
+    ];
+    let exit_code = outcomes
+        .into_iter()
+        .filter(|(_, happened)| *happened)
+        .map(|(outcome, _)| config.exit_code(outcome))
+        .find(|&code| code != 0)
+        .map_or(ExitCode::SUCCESS, ExitCode::from);
 
-    let Some(message_file) = cli.message_file else {
+    if cli.explain {
+        eprint!("{}", report::explain(&changes));
+    }
+
+    // Printed for every format, the records of `--format json` have no place for them
+    for violation in &violations {
+        eprintln!("{violation}");
+    }
+
+    match cli.format {
+        OutputFormat::Trailer => {}
+        OutputFormat::Json => {
+            println!("{}", report::json(&changes));
+            return Ok(exit_code);
+        }
+        OutputFormat::Ndjson => {
+            print!("{}", report::ndjson(&changes));
+            return Ok(exit_code);
+        }
+    }
+
+    if !cli.explain {
+        for warning in &changes.warnings {
+            eprintln!("{warning}");
+        }
+    }
+
+    let report_untagged =
+        config.untagged() != Policy::Ignore || config.exit_code(Outcome::Untagged) != 0;
+    if report_untagged && !changes.untagged.is_empty() {
+        eprintln!("Changed scenarios without a tracking number:");
+        for scenario in &changes.untagged {
+            eprintln!("  {scenario}");
+        }
+    }
+
+    let trailer = format_issue_references(
+        &changes.numbers,
+        config.width(),
+        &format!("{}: ", config.trailer()),
+    );
+
+    let Some(message_file) = &cli.message_file else {
         // if called without args, assume cli usage and print the trailer
         print!("{trailer}");
-        return;
+        return Ok(exit_code);
     };
 
     if !cli
@@ -35,31 +138,457 @@ fn main() {
         .as_ref()
         .is_none_or(|src| src == "template" || src == "message")
     {
-        return;
+        return Ok(exit_code);
     }
 
-    let mut msg_file = File::options()
-        .read(true)
-        .write(true)
-        .open(&message_file)
-        .unwrap();
+    add_trailer(message_file, &trailer).map_err(|source| HookError::MessageFile {
+        path: message_file.clone(),
+        source,
+    })?;
+
+    Ok(exit_code)
+}
+
+fn run_command(command: &Command) -> Result<ExitCode, HookError> {
+    let repo = Repository::open_from_env().map_err(HookError::OpenRepository)?;
+
+    match command {
+        Command::Install { notes } | Command::Uninstall { notes } | Command::Status { notes } => {
+            let kind = if *notes {
+                HookKind::Notes
+            } else {
+                HookKind::Trailer
+            };
+            manage_hook(&repo, kind, command)
+        }
+        Command::Doctor { config } => {
+            let checks = doctor::diagnose(&repo, Config::from(config));
+            for check in &checks {
+                println!("{check}");
+            }
+            let problems = checks.iter().any(|c| c.severity == Severity::Problem);
+            Ok(if problems {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            })
+        }
+        Command::Assign {
+            dry_run,
+            allocation,
+            config,
+        } => {
+            let config = load_config(
+                &repo,
+                Config {
+                    allocation: allocation.allocation,
+                    allocation_range: allocation.allocation_range,
+                    allocation_file: allocation.allocation_file.clone(),
+                    ..Config::from(config)
+                },
+            )?;
+            let source = config.number_source().map_err(HookError::Config)?;
+            let assignments = assign_numbers(&repo, &config.options(), &source, *dry_run)
+                .map_err(HookError::Assign)?;
+
+            if assignments.is_empty() {
+                println!("No new scenarios without tracking number");
+            }
+            let prefix = config.options().test_prefix;
+            for assignment in &assignments {
+                println!("{}: @{prefix}{}", assignment.test, assignment.number);
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Lint { revision, config } => {
+            let config = load_config(&repo, Config::from(config))?;
+            let opts = config.options();
+            let revision = revision.clone().map_or(Revision::Index, Revision::Commit);
+            let scanned = scan(&repo, &revision, &opts).map_err(HookError::Detect)?;
+
+            let issues = lint(&scanned, &opts.test_prefix);
+            for issue in &issues {
+                println!("{issue}");
+            }
+
+            // Unlike the other issues, the catalog only fails the lint if configured so
+            let mut catalog_fails = false;
+            if let Some(catalog) = load_catalog(&repo, &config)? {
+                if config.unknown_numbers() != Policy::Ignore {
+                    let catalog_issues = check_catalog(&scanned, &opts.test_prefix, &catalog);
+                    for issue in &catalog_issues {
+                        println!("{issue}");
+                    }
+                    catalog_fails = !catalog_issues.is_empty()
+                        && config.exit_code(Outcome::UnknownNumbers) != 0;
+                }
+            }
+
+            Ok(if issues.is_empty() && !catalog_fails {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        Command::List {
+            revision,
+            format,
+            all,
+            config,
+        } => {
+            let config = load_config(&repo, Config::from(config))?;
+            let revision = revision.clone().map_or(Revision::Index, Revision::Commit);
+            let scanned = scan(&repo, &revision, &config.options()).map_err(HookError::Detect)?;
+
+            for warning in &scanned.warnings {
+                eprintln!("{warning}");
+            }
+            let mut tests: Vec<_> = scanned
+                .tests
+                .into_iter()
+                .filter(|test| *all || test.number.is_some())
+                .collect();
+            // Untagged test cases last, the scan already sorted by path and line
+            tests.sort_by_key(|test| (test.number.is_none(), test.number));
+
+            print_listing(&report::list(&tests, *format), *format);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::History {
+            numbers,
+            range,
+            format,
+            no_cache,
+            config,
+        } => {
+            let config = load_config(&repo, Config::from(config))?;
+            let opts = config.options();
+            let entries = with_cache(&repo, &opts, *no_cache, |cache| {
+                history(&repo, range, numbers, &opts, cache)
+            })
+            .map_err(HookError::History)?;
+
+            print_listing(&report::history(&entries, *format), *format);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Notes {
+            revision,
+            range,
+            dry_run,
+            notes,
+            config,
+        } => {
+            let config = load_config(
+                &repo,
+                Config {
+                    notes_ref: notes.notes_ref.clone(),
+                    notes_format: notes.notes_format,
+                    ..Config::from(config)
+                },
+            )?;
+            let commits = match range {
+                Some(range) => history::commits(&repo, range),
+                None => history::commit(&repo, revision).map(|commit| vec![commit]),
+            }
+            .map_err(HookError::History)?;
+
+            let notes = annotate(&repo, &commits, &config, *dry_run).map_err(HookError::Notes)?;
+            for note in &notes {
+                if *dry_run {
+                    print!("{}\n{}", note.commit, note.text);
+                } else {
+                    println!("Added note to {}", note.commit);
+                }
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Export {
+            range,
+            format,
+            key_prefix,
+            no_cache,
+            config,
+        } => {
+            let config = load_config(&repo, Config::from(config))?;
+            let opts = config.options();
+            let export = with_cache(&repo, &opts, *no_cache, |cache| {
+                changed_in_range(&repo, range, &opts, cache)
+            })
+            .map_err(HookError::History)?;
+
+            for warning in &export.warnings {
+                eprintln!("{warning}");
+            }
+            let output = report::export(&export.tests, *format, key_prefix.as_deref());
+            match format {
+                ExportFormat::Csv => print!("{output}"),
+                _ => println!("{output}"),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Audit {
+            range,
+            format,
+            no_cache,
+            config,
+        } => {
+            let config = load_config(&repo, Config::from(config))?;
+            let opts = config.options();
+            let audits = with_cache(&repo, &opts, *no_cache, |cache| {
+                audit(&repo, range, config.trailer(), &opts, cache)
+            })
+            .map_err(HookError::History)?;
+
+            print_listing(&report::audit(&audits, *format), *format);
+            Ok(if audits.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        #[cfg(feature = "notify")]
+        Command::Notify {
+            range,
+            dry_run,
+            notify,
+            no_cache,
+            config,
+        } => {
+            let config = load_config(
+                &repo,
+                Config {
+                    notify_url: notify.notify_url.clone(),
+                    notify_payload: notify.notify_payload.clone(),
+                    notify_retries: notify.notify_retries,
+                    ..Config::from(config)
+                },
+            )?;
+            let notifier = Notifier::from_config(&config).map_err(HookError::Notify)?;
+            let opts = config.options();
+            let changes = with_cache(&repo, &opts, *no_cache, |cache| {
+                last_changes(&repo, range, &opts, cache)
+            })
+            .map_err(HookError::History)?;
+
+            let mut failed = false;
+            for notification in notifications(changes, range) {
+                if *dry_run {
+                    let request = notifier.request(&notification).map_err(HookError::Notify)?;
+                    println!("POST {}\n{}", request.url, request.body);
+                    continue;
+                }
+                match notifier.send(&notification) {
+                    Ok(delivery) => println!("#{}: {}", notification.number, delivery.status),
+                    Err(err) => {
+                        eprintln!("#{}: {err}", notification.number);
+                        failed = true;
+                    }
+                }
+            }
+            Ok(if failed {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            })
+        }
+    }
+}
+
+/// Read the configured catalog, relative to the repository root.
+fn load_catalog(repo: &Repository, config: &Config) -> Result<Option<Catalog>, HookError> {
+    let Some(path) = &config.catalog else {
+        return Ok(None);
+    };
+    let root = repo.workdir().unwrap_or_else(|| repo.path());
+    Catalog::load(&root.join(path))
+        .map(Some)
+        .map_err(HookError::Catalog)
+}
+
+/// Load the configuration of the repository, overridden by the command line.
+fn load_config(repo: &Repository, overrides: Config) -> Result<Config, HookError> {
+    Ok(Config::load(repo)
+        .map_err(HookError::Config)?
+        .merge(overrides))
+}
+
+/// Run a query with the cache of the repository, or an empty one with `--no-cache`.
+///
+/// The cache is stored afterwards, a failure is only reported:
+/// the result is still valid without the cache, it's only slower next time.
+fn with_cache<T>(
+    repo: &Repository,
+    opts: &Options,
+    no_cache: bool,
+    query: impl FnOnce(&mut NumberCache) -> T,
+) -> T {
+    let mut cache = if no_cache {
+        NumberCache::in_memory()
+    } else {
+        NumberCache::load(repo, opts)
+    };
+    let result = query(&mut cache);
+    match cache.misses() {
+        _ if no_cache => {}
+        0 => {}
+        1 => eprintln!("show-changed-tests: 1 commit was not cached yet"),
+        misses => eprintln!("show-changed-tests: {misses} commits were not cached yet"),
+    }
+    if let Err(err) = cache.save() {
+        eprintln!("show-changed-tests: {err}");
+    }
+    result
+}
+
+/// Print the output of a listing command, JSON lacks the final line break.
+fn print_listing(output: &str, format: ListFormat) {
+    match format {
+        ListFormat::Json => println!("{output}"),
+        _ => print!("{output}"),
+    }
+}
+
+/// Install, remove or check the hook.
+fn manage_hook(
+    repo: &Repository,
+    kind: HookKind,
+    command: &Command,
+) -> Result<ExitCode, HookError> {
+    let hook = Hook::locate_kind(repo, kind).map_err(HookError::Install)?;
+    let path = hook.path().display();
+
+    match command {
+        Command::Install { .. } => match hook.install().map_err(HookError::Install)? {
+            HookStatus::Missing => println!("Installed {path}"),
+            HookStatus::Installed { .. } => println!("Updated {path}"),
+            HookStatus::Foreign => println!(
+                "Installed {path}, the existing hook was moved to {} and runs first",
+                hook.chained_path().display()
+            ),
+        },
+        Command::Uninstall { .. } => match hook.uninstall().map_err(HookError::Install)? {
+            Removal::NotInstalled => println!("{path} is not installed"),
+            Removal::Removed { restored: false } => println!("Removed {path}"),
+            Removal::Removed { restored: true } => {
+                println!("Removed {path}, the previous hook was restored")
+            }
+        },
+        _ => match hook.status().map_err(HookError::Install)? {
+            HookStatus::Missing => {
+                println!("{path} is not installed");
+                return Ok(ExitCode::FAILURE);
+            }
+            HookStatus::Installed { chained: false } => println!("{path} is installed"),
+            HookStatus::Installed { chained: true } => println!(
+                "{path} is installed and runs {} first",
+                hook.chained_path().display()
+            ),
+            HookStatus::Foreign => {
+                println!("{path} belongs to another tool");
+                return Ok(ExitCode::FAILURE);
+            }
+        },
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Insert the trailer into the commit message file.
+fn add_trailer(message_file: &Path, trailer: &str) -> io::Result<()> {
+    let mut msg_file = File::options().read(true).write(true).open(message_file)?;
     let mut message = String::new();
-    msg_file.read_to_string(&mut message).unwrap();
+    msg_file.read_to_string(&mut message)?;
+
+    let message = extend_message(&message, trailer);
+
+    msg_file.seek(SeekFrom::Start(0))?;
+    msg_file.set_len(0)?;
+    msg_file.write_all(message.as_bytes())
+}
 
-    let message = extend_message(&message, &trailer);
+/// Everything that can go wrong while running the hook or a command.
+#[derive(Debug)]
+enum HookError {
+    OpenRepository(git2::Error),
+    Config(ConfigError),
+    Detect(ExtractNumberError),
+    MessageFile {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Install(InstallError),
+    Assign(AssignError),
+    History(HistoryError),
+    Notes(NotesError),
+    Catalog(CatalogError),
+    #[cfg(feature = "notify")]
+    Notify(NotifyError),
+}
+
+impl Display for HookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookError::OpenRepository(err) => write!(f, "Failed to open the git repository: {err}"),
+            HookError::Config(err) => {
+                write!(f, "Failed to load configuration: {err}")?;
+                // The message of the configuration error leaves the details to its source
+                match err.source() {
+                    Some(source) => write!(f, ": {source}"),
+                    None => Ok(()),
+                }
+            }
+            HookError::Detect(err) => write!(f, "Failed to detect changed tests: {err}"),
+            HookError::MessageFile { path, source } => write!(
+                f,
+                "Failed to update the commit message in {}: {source}",
+                path.display()
+            ),
+            HookError::Install(err) => err.fmt(f),
+            HookError::Assign(err) => write!(f, "Failed to assign tracking numbers: {err}"),
+            HookError::History(err) => write!(f, "Failed to walk the history: {err}"),
+            HookError::Notes(err) => write!(f, "Failed to add git notes: {err}"),
+            HookError::Catalog(err) => err.fmt(f),
+            #[cfg(feature = "notify")]
+            HookError::Notify(err) => write!(f, "Failed to notify the server: {err}"),
+        }
+    }
+}
 
-    msg_file.seek(SeekFrom::Start(0)).unwrap();
-    msg_file.set_len(0).unwrap();
-    msg_file.write_all(message.as_bytes()).unwrap();
+impl std::error::Error for HookError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HookError::OpenRepository(err) => Some(err),
+            HookError::Config(err) => Some(err),
+            HookError::Detect(err) => Some(err),
+            HookError::MessageFile { source, .. } => Some(source),
+            HookError::Install(err) => Some(err),
+            HookError::Assign(err) => Some(err),
+            HookError::History(err) => Some(err),
+            HookError::Notes(err) => Some(err),
+            HookError::Catalog(err) => Some(err),
+            #[cfg(feature = "notify")]
+            HookError::Notify(err) => Some(err),
+        }
+    }
 }
 
 #[derive(Debug, Parser, Clone)]
+#[clap(args_conflicts_with_subcommands = true)]
 struct Cli {
-    #[clap(long, default_value = "tc:")]
-    prefix: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    config: ConfigArgs,
 
-    #[clap(long, default_value = "Tests")]
-    trailer: String,
+    /// Print the changes in a machine readable format instead of the trailer.
+    /// The commit message is not modified in this case.
+    #[clap(long, value_enum, default_value_t)]
+    format: OutputFormat,
+
+    /// Print to stderr why each number was reported, with the changed lines
+    #[clap(long)]
+    explain: bool,
 
     message_file: Option<PathBuf>,
 
@@ -68,10 +597,347 @@ struct Cli {
     hash: Option<String>,
 }
 
-impl From<Cli> for Options {
-    fn from(value: Cli) -> Self {
+/// Commands besides running as hook.
+#[derive(Debug, Subcommand, Clone)]
+enum Command {
+    /// Install the prepare-commit-msg hook, keeping an existing hook
+    Install {
+        /// Install the post-commit hook that stores the changed tests in git notes instead
+        #[clap(long)]
+        notes: bool,
+    },
+    /// Remove the hook and restore a previously existing hook
+    Uninstall {
+        /// Remove the post-commit hook for git notes instead
+        #[clap(long)]
+        notes: bool,
+    },
+    /// Check whether the hook is installed, exits with 1 if not
+    Status {
+        /// Check the post-commit hook for git notes instead
+        #[clap(long)]
+        notes: bool,
+    },
+    /// Check why no trailer is added: hook, pre-commit, configuration and test files
+    Doctor {
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// Tag new staged scenarios without tracking number with the next free numbers
+    Assign {
+        /// Only print the numbers, without changing any file
+        #[clap(long)]
+        dry_run: bool,
+
+        #[clap(flatten)]
+        allocation: AllocationArgs,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// Check for duplicate, malformed and multiple tracking numbers, exits with 1 if any
+    Lint {
+        /// Check the files of this commit instead of the staged files, e.g. HEAD
+        #[clap(long, value_name = "REV")]
+        revision: Option<String>,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// List the tracking numbers of all test cases with file, line, feature, scenario and tags
+    List {
+        /// List the files of this commit instead of the staged files, e.g. HEAD
+        #[clap(long, value_name = "REV")]
+        revision: Option<String>,
+
+        #[clap(long, value_enum, default_value_t)]
+        format: ListFormat,
+
+        /// Include test cases without tracking number
+        #[clap(long)]
+        all: bool,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// List the commits that changed the given tracking numbers, or any test case
+    History {
+        /// Tracking numbers to look for, without prefix
+        #[clap(value_name = "ID")]
+        numbers: Vec<u32>,
+
+        /// Commits to check, e.g. v1.0..HEAD. The default walks the whole history, which is slow until it is cached
+        #[clap(long, default_value = "HEAD")]
+        range: String,
+
+        #[clap(long, value_enum, default_value_t)]
+        format: ListFormat,
+
+        /// Detect the changes of every commit again instead of using the cache
+        #[clap(long)]
+        no_cache: bool,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// Store the changed tests of a commit in a git note, e.g. in a post-commit hook
+    Notes {
+        /// Commit to annotate
+        #[clap(
+            value_name = "COMMIT",
+            default_value = "HEAD",
+            conflicts_with = "range"
+        )]
+        revision: String,
+
+        /// Annotate every commit in a range instead, e.g. v1.0..HEAD
+        #[clap(long)]
+        range: Option<String>,
+
+        /// Only print the notes, without writing them
+        #[clap(long)]
+        dry_run: bool,
+
+        #[clap(flatten)]
+        notes: NotesArgs,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// Export the test cases changed in a range for import into a test management tool
+    Export {
+        /// Commits to check, e.g. v1.0..v1.1. The default walks the whole history, which is slow until it is cached
+        #[clap(long, default_value = "HEAD")]
+        range: String,
+
+        #[clap(long, value_enum, default_value_t)]
+        format: ExportFormat,
+
+        /// Prefix of the Jira issue keys to update with the Xray import, e.g. BANK-
+        #[clap(long, value_name = "PREFIX")]
+        key_prefix: Option<String>,
+
+        /// Detect the changes of every commit again instead of using the cache
+        #[clap(long)]
+        no_cache: bool,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// Compare the trailer of each commit with its changes, exits with 1 on differences
+    Audit {
+        /// Commits to check, e.g. origin/main..HEAD. The default walks the whole history, which is slow until it is cached
+        #[clap(long, default_value = "HEAD")]
+        range: String,
+
+        #[clap(long, value_enum, default_value_t)]
+        format: ListFormat,
+
+        /// Detect the changes of every commit again instead of using the cache
+        #[clap(long)]
+        no_cache: bool,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+    /// Post each tracking number changed in a range to an HTTP endpoint, exits with 1 on failures
+    #[cfg(feature = "notify")]
+    Notify {
+        /// Commits to check, e.g. origin/main..HEAD. The default walks the whole history, which is slow until it is cached
+        #[clap(long, default_value = "HEAD")]
+        range: String,
+
+        /// Only print the requests, without sending them
+        #[clap(long)]
+        dry_run: bool,
+
+        #[clap(flatten)]
+        notify: NotifyArgs,
+
+        /// Detect the changes of every commit again instead of using the cache
+        #[clap(long)]
+        no_cache: bool,
+
+        #[clap(flatten)]
+        config: ConfigArgs,
+    },
+}
+
+// Options that are not given fall back to `git config` and `.show-changed-tests.toml`,
+// so they must not have default values here.
+#[derive(Debug, Args, Clone, Default)]
+struct ConfigArgs {
+    /// Prefix of tags that contain the tracking number [default: tc:]
+    #[clap(long)]
+    prefix: Option<String>,
+
+    /// Name of the trailer [default: Tests]
+    #[clap(long)]
+    trailer: Option<String>,
+
+    /// Maximum width of a trailer line [default: 72]
+    #[clap(long)]
+    width: Option<usize>,
+
+    /// What to do with changed scenarios that have no tracking number [default: ignore]
+    #[clap(long, value_enum)]
+    untagged: Option<Policy>,
+
+    /// Glob pattern of files that contain test cases, can be repeated [default: **/*.feature]
+    #[clap(long = "include", value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Glob pattern of files to skip, can be repeated
+    #[clap(long = "exclude", value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Glob pattern of files with step definitions, can be repeated.
+    /// Scenarios using a changed step definition are reported as changed.
+    #[clap(long = "step-definitions", value_name = "GLOB")]
+    step_definitions: Vec<String>,
+
+    /// Report scenarios that reference a changed file, e.g. `Given the payload "data.json"`
+    #[clap(long, overrides_with = "no_fixture_references")]
+    fixture_references: bool,
+
+    /// Don't report scenarios that reference a changed file, even if configured
+    #[clap(long, overrides_with = "fixture_references")]
+    no_fixture_references: bool,
+
+    /// Language of Gherkin files without `# language:` header, e.g. `de` [default: en]
+    #[clap(long, value_parser = parse_language)]
+    language: Option<String>,
+
+    /// Exit with 1 on errors, parse errors and untagged scenarios, e.g. in CI
+    #[clap(long, overrides_with = "no_strict")]
+    strict: bool,
+
+    /// Use the lenient exit codes, even if strict mode is configured
+    #[clap(long, overrides_with = "strict")]
+    no_strict: bool,
+
+    /// Exit code if the hook fails, e.g. outside of a git repository [default: 0]
+    #[clap(long, value_name = "CODE")]
+    error_exit_code: Option<u8>,
+
+    /// Exit code if a test file or step pattern can not be parsed [default: 0]
+    #[clap(long, value_name = "CODE")]
+    parse_error_exit_code: Option<u8>,
+
+    /// Exit code if changed scenarios have no tracking number [default: 0]
+    #[clap(long, value_name = "CODE")]
+    untagged_exit_code: Option<u8>,
+
+    /// Exit code if no changed tests are found [default: 0]
+    #[clap(long, value_name = "CODE")]
+    no_changes_exit_code: Option<u8>,
+
+    /// CSV or JSON file with the known tracking numbers, optionally with a status like `retired`
+    #[clap(long, value_name = "PATH")]
+    catalog: Option<PathBuf>,
+
+    /// What to do with numbers that are unknown or retired in the catalog [default: warn]
+    #[clap(long, value_enum)]
+    unknown_numbers: Option<Policy>,
+
+    /// Exit code if changed tests have an unknown or retired number [default: 0]
+    #[clap(long, value_name = "CODE")]
+    unknown_numbers_exit_code: Option<u8>,
+}
+
+#[derive(Debug, Args, Clone, Default)]
+struct AllocationArgs {
+    /// Where the numbers come from [default: next, or range/file if configured]
+    #[clap(long, value_enum)]
+    allocation: Option<Allocation>,
+
+    /// Numbers reserved for new scenarios, e.g. 5000-5999
+    #[clap(long, value_name = "RANGE")]
+    allocation_range: Option<NumberRange>,
+
+    /// File listing the numbers reserved for new scenarios, used ones are removed
+    #[clap(long, value_name = "PATH")]
+    allocation_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args, Clone, Default)]
+struct NotesArgs {
+    /// Ref of the git notes [default: refs/notes/tests]
+    #[clap(long, value_name = "REF")]
+    notes_ref: Option<String>,
+
+    /// Whether a note contains the trailer or the JSON document [default: trailer]
+    #[clap(long, value_enum)]
+    notes_format: Option<NoteFormat>,
+}
+
+#[cfg(feature = "notify")]
+#[derive(Debug, Args, Clone, Default)]
+struct NotifyArgs {
+    /// URL to post to, `{id}` and `{commit}` are replaced
+    #[clap(long, value_name = "URL")]
+    notify_url: Option<String>,
+
+    /// JSON payload, `{id}`, `{commit}` and `{range}` are replaced [default: {"id": {id}, "commit": {commit}}]
+    #[clap(long, value_name = "JSON")]
+    notify_payload: Option<String>,
+
+    /// How often a failed request is retried [default: 3]
+    #[clap(long, value_name = "COUNT")]
+    notify_retries: Option<u32>,
+}
+
+impl From<&ConfigArgs> for Config {
+    fn from(value: &ConfigArgs) -> Self {
         Self {
-            test_prefix: value.prefix,
+            prefix: value.prefix.clone(),
+            trailer: value.trailer.clone(),
+            width: value.width,
+            untagged: value.untagged,
+            include: (!value.include.is_empty()).then(|| value.include.clone()),
+            exclude: (!value.exclude.is_empty()).then(|| value.exclude.clone()),
+            step_definitions: (!value.step_definitions.is_empty())
+                .then(|| value.step_definitions.clone()),
+            fixtures: None,
+            fixture_references: flag(value.fixture_references, value.no_fixture_references),
+            language: value.language.clone(),
+            strict: flag(value.strict, value.no_strict),
+            error_exit_code: value.error_exit_code,
+            parse_error_exit_code: value.parse_error_exit_code,
+            untagged_exit_code: value.untagged_exit_code,
+            no_changes_exit_code: value.no_changes_exit_code,
+            catalog: value.catalog.clone(),
+            unknown_numbers: value.unknown_numbers,
+            unknown_numbers_exit_code: value.unknown_numbers_exit_code,
+            // Only used by `assign`, see `AllocationArgs`
+            allocation: None,
+            allocation_range: None,
+            allocation_file: None,
+            // Only used by `notes`, see `NotesArgs`
+            notes_ref: None,
+            notes_format: None,
+            // Only used by `notify`, see `NotifyArgs`
+            notify_url: None,
+            notify_payload: None,
+            notify_headers: None,
+            notify_retries: None,
         }
     }
 }
+
+/// The value of a flag with a `--no-` variant, `None` if neither is given.
+fn flag(enabled: bool, disabled: bool) -> Option<bool> {
+    match (enabled, disabled) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_language(language: &str) -> Result<String, String> {
+    if gherkin::is_language_supported(language) {
+        Ok(language.to_owned())
+    } else {
+        Err(format!("no Gherkin keywords for language '{language}'"))
+    }
+}
@@ -0,0 +1,146 @@
+//! Storing the changed tests in git notes instead of the commit message.
+//!
+//! Some repositories do not allow rewriting commit messages, e.g. because commits are signed.
+//! A note is attached to the commit without changing it, under a separate notes ref,
+//! and can be shown with `git log --notes=tests`.
+
+use std::{env, fmt::Display};
+
+use git2::{Commit, Oid, Repository, Signature};
+use serde::Deserialize;
+
+use crate::{
+    changed_tests_in_commit, config::Config, format_issue_references, report, ExtractNumberError,
+};
+
+/// Notes ref used if none is configured.
+pub const DEFAULT_NOTES_REF: &str = "refs/notes/tests";
+
+/// How the changed tests are written into a note.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteFormat {
+    /// The trailer, as it would be added to the commit message.
+    #[default]
+    Trailer,
+    /// The JSON document of `--format json`, including the reasons.
+    Json,
+}
+
+/// A note attached to a commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub commit: Oid,
+    pub text: String,
+}
+
+/// Possible errors while writing notes.
+#[derive(Debug)]
+pub enum NotesError {
+    Detect(ExtractNumberError),
+    /// The author of the notes is not configured.
+    Signature(git2::Error),
+    /// The note could not be written.
+    Write {
+        commit: Oid,
+        source: git2::Error,
+    },
+}
+
+/// Attach the changed tests of each commit as a note, replacing an existing one.
+///
+/// Commits without changed tests get no note.
+/// Returns the notes, which are only printed but not written if `dry_run` is set.
+pub fn annotate(
+    repo: &Repository,
+    commits: &[Commit],
+    config: &Config,
+    dry_run: bool,
+) -> Result<Vec<Note>, NotesError> {
+    let opts = config.options();
+    let signature = if dry_run {
+        None
+    } else {
+        Some(signature(repo).map_err(NotesError::Signature)?)
+    };
+
+    let mut notes = Vec::new();
+    for commit in commits {
+        let changes = changed_tests_in_commit(repo, commit, &opts).map_err(NotesError::Detect)?;
+        if changes.tests.is_empty() {
+            continue;
+        }
+
+        let text = match config.notes_format() {
+            NoteFormat::Trailer => {
+                if changes.numbers.is_empty() {
+                    continue;
+                }
+                format_issue_references(
+                    &changes.numbers,
+                    config.width(),
+                    &format!("{}: ", config.trailer()),
+                ) + "\n"
+            }
+            NoteFormat::Json => report::json(&changes) + "\n",
+        };
+
+        if let Some(signature) = &signature {
+            repo.note(
+                signature,
+                signature,
+                Some(config.notes_ref()),
+                commit.id(),
+                &text,
+                true,
+            )
+            .map_err(|source| NotesError::Write {
+                commit: commit.id(),
+                source,
+            })?;
+        }
+        notes.push(Note {
+            commit: commit.id(),
+            text,
+        });
+    }
+    Ok(notes)
+}
+
+/// The identity git would use as committer.
+///
+/// libgit2 only reads `user.name` and `user.email`,
+/// but git prefers the environment variables, which are common in CI.
+fn signature(repo: &Repository) -> Result<Signature<'static>, git2::Error> {
+    match (
+        env::var("GIT_COMMITTER_NAME"),
+        env::var("GIT_COMMITTER_EMAIL"),
+    ) {
+        (Ok(name), Ok(email)) => Signature::now(&name, &email),
+        _ => repo.signature(),
+    }
+}
+
+impl Display for NotesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotesError::Detect(err) => err.fmt(f),
+            NotesError::Signature(err) => {
+                write!(f, "Failed to determine the author of the notes: {err}")
+            }
+            NotesError::Write { commit, source } => {
+                write!(f, "Failed to write the note of {commit}: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NotesError::Detect(err) => Some(err),
+            NotesError::Signature(err) => Some(err),
+            NotesError::Write { source, .. } => Some(source),
+        }
+    }
+}
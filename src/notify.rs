@@ -0,0 +1,274 @@
+//! Posting the changed test cases to a test management server.
+//!
+//! For every tracking number changed in a revision range, a JSON payload is posted
+//! to a URL built from a template. Both are configured, so any server can be notified:
+//!
+//! ```toml
+//! notify-url = "https://tracker.example.com/api/tests/{id}/changed"
+//! notify-payload = '{"test": {id}, "commit": {commit}, "source": "ci"}'
+//!
+//! [notify-headers]
+//! Authorization = "Bearer ${TRACKER_TOKEN}"
+//! ```
+//!
+//! In the payload, `{id}` is replaced by the number, `{commit}` by the id of the last commit
+//! that changed the test case and `{range}` by the revision range, all as JSON values.
+//! In the URL, `{id}` and `{commit}` are replaced by the plain values.
+//! Failed requests are retried with an increasing delay.
+
+use std::{env, fmt::Display, thread, time::Duration};
+
+use git2::Oid;
+
+use crate::{config::Config, history::LastChange};
+
+/// Payload posted if none is configured.
+pub const DEFAULT_PAYLOAD: &str = r#"{"id": {id}, "commit": {commit}}"#;
+
+/// A changed test case to report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub number: u32,
+    /// The last commit of the range that changed the test case.
+    pub commit: Oid,
+    /// The revision range, e.g. `origin/main..HEAD`.
+    pub range: String,
+}
+
+/// A request of the notifier, with all placeholders replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// The response to a successful request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delivery {
+    /// HTTP status code.
+    pub status: u16,
+    /// Number of requests, including the retries.
+    pub attempts: u32,
+}
+
+/// Posts notifications with the configured URL, payload and headers.
+#[derive(Debug, Clone)]
+pub struct Notifier {
+    url: String,
+    payload: String,
+    headers: Vec<(String, String)>,
+    retries: u32,
+    retry_delay: Duration,
+    agent: ureq::Agent,
+}
+
+/// Possible errors while notifying the server.
+#[derive(Debug)]
+pub enum NotifyError {
+    /// No URL is configured.
+    MissingUrl,
+    /// A header refers to an environment variable that is not set.
+    MissingVariable(String),
+    /// The payload with the placeholders replaced is no valid JSON.
+    InvalidPayload {
+        payload: String,
+        source: serde_json::Error,
+    },
+    /// The server could not be reached, even after retrying.
+    Transport {
+        url: String,
+        attempts: u32,
+        message: String,
+    },
+    /// The server responded with an error status.
+    Status {
+        url: String,
+        attempts: u32,
+        status: u16,
+    },
+}
+
+/// The notification for every changed number, with its last commit.
+pub fn notifications(
+    changes: impl IntoIterator<Item = (u32, LastChange)>,
+    range: &str,
+) -> Vec<Notification> {
+    changes
+        .into_iter()
+        .map(|(number, change)| Notification {
+            number,
+            commit: change.commit,
+            range: range.to_owned(),
+        })
+        .collect()
+}
+
+impl Notifier {
+    /// Create a notifier from the `notify-*` settings.
+    pub fn from_config(config: &Config) -> Result<Self, NotifyError> {
+        let url = config.notify_url.clone().ok_or(NotifyError::MissingUrl)?;
+        let headers = config
+            .notify_headers
+            .iter()
+            .flatten()
+            .map(|(name, value)| Ok((name.clone(), expand_variables(value)?)))
+            .collect::<Result<_, NotifyError>>()?;
+
+        Ok(Self {
+            url,
+            payload: config
+                .notify_payload
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PAYLOAD.to_owned()),
+            headers,
+            retries: config.notify_retries.unwrap_or(3),
+            retry_delay: Duration::from_secs(1),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+        })
+    }
+
+    /// Wait `delay` before the first retry, doubling it for every further one.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// The request for a notification, e.g. to print it in a dry run.
+    pub fn request(&self, notification: &Notification) -> Result<Request, NotifyError> {
+        let url = self
+            .url
+            .replace("{id}", &notification.number.to_string())
+            .replace("{commit}", &notification.commit.to_string());
+
+        let string = |value: &str| serde_json::Value::from(value).to_string();
+        let body = self
+            .payload
+            .replace("{id}", &notification.number.to_string())
+            .replace("{commit}", &string(&notification.commit.to_string()))
+            .replace("{range}", &string(&notification.range));
+        if let Err(source) = serde_json::from_str::<serde_json::Value>(&body) {
+            return Err(NotifyError::InvalidPayload {
+                payload: body,
+                source,
+            });
+        }
+
+        Ok(Request {
+            url,
+            headers: self.headers.clone(),
+            body,
+        })
+    }
+
+    /// Post a notification, retrying on network errors and server errors.
+    pub fn send(&self, notification: &Notification) -> Result<Delivery, NotifyError> {
+        let request = self.request(notification)?;
+
+        let mut attempts = 0;
+        loop {
+            if attempts > 0 {
+                thread::sleep(self.retry_delay * 2u32.saturating_pow(attempts - 1));
+            }
+            attempts += 1;
+
+            let mut http = self
+                .agent
+                .post(&request.url)
+                .set("Content-Type", "application/json");
+            for (name, value) in &request.headers {
+                http = http.set(name, value);
+            }
+
+            let error = match http.send_string(&request.body) {
+                Ok(response) => {
+                    return Ok(Delivery {
+                        status: response.status(),
+                        attempts,
+                    })
+                }
+                Err(ureq::Error::Status(status, _)) => NotifyError::Status {
+                    url: request.url.clone(),
+                    attempts,
+                    status,
+                },
+                Err(ureq::Error::Transport(transport)) => NotifyError::Transport {
+                    url: request.url.clone(),
+                    attempts,
+                    message: transport.to_string(),
+                },
+            };
+
+            // Client errors will not go away by trying again
+            let retry = match error {
+                NotifyError::Status { status, .. } => status >= 500 || status == 429,
+                _ => true,
+            };
+            if !retry || attempts > self.retries {
+                return Err(error);
+            }
+        }
+    }
+}
+
+/// Replace `${VAR}` with the value of the environment variable.
+fn expand_variables(value: &str) -> Result<String, NotifyError> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + len];
+        let variable = env::var(name).map_err(|_| NotifyError::MissingVariable(name.to_owned()))?;
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&variable);
+        rest = &rest[start + len + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+impl Display for NotifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotifyError::MissingUrl => f.write_str("Missing value for 'notify-url'"),
+            NotifyError::MissingVariable(name) => {
+                write!(
+                    f,
+                    "Environment variable {name} of a notify header is not set"
+                )
+            }
+            NotifyError::InvalidPayload { payload, source } => {
+                write!(f, "Invalid JSON payload {payload}: {source}")
+            }
+            NotifyError::Transport {
+                url,
+                attempts,
+                message,
+            } => write!(
+                f,
+                "Failed to post to {url} after {attempts} attempt(s): {message}"
+            ),
+            NotifyError::Status {
+                url,
+                attempts,
+                status,
+            } => write!(
+                f,
+                "Failed to post to {url} after {attempts} attempt(s): status {status}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NotifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NotifyError::InvalidPayload { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
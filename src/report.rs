@@ -0,0 +1,712 @@
+//! Output of the detected changes beyond the trailer.
+//!
+//! The machine readable formats for scripts and CI contain the same records:
+//! every changed test case with the reason and the changed line ranges,
+//! the changed scenarios without tracking number and the warnings, like files that could not be parsed.
+//! [`explain`] prints the same information as a tree for humans.
+//! [`list`] prints all test cases of a revision, not only the changed ones,
+//! [`history`] prints the commits that changed them and [`audit`] the commits with a wrong trailer.
+//! [`export`] prints the changed test cases of a range for test management tools.
+
+use std::{borrow::Cow, path::Path};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    audit::CommitAudit,
+    export::ExportedTest,
+    history::{format_date, HistoryEntry},
+    scan::ScannedTest,
+    ChangeKind, ChangedLine, ChangedTest, ChangedTests, TestCase, Version, Warning,
+};
+
+/// How the detected changes are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// The trailer for the commit message.
+    #[default]
+    Trailer,
+    /// A single JSON document.
+    Json,
+    /// One JSON record per line.
+    Ndjson,
+}
+
+/// How the test cases of a revision are listed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    /// One line per test case.
+    #[default]
+    Text,
+    /// Comma separated values with a header line.
+    Csv,
+    /// A JSON array.
+    Json,
+}
+
+/// An inventory of test cases with their tracking number, location, feature and tags.
+///
+/// ```text
+/// #1001 features/withdraw.feature:8: Withdrawing cash: Successful withdrawal @smoke @tc:1001
+/// ```
+///
+/// Tags are separated by spaces in CSV, and test cases without tracking number have an empty id.
+pub fn list(tests: &[ScannedTest], format: ListFormat) -> String {
+    match format {
+        ListFormat::Text => tests
+            .iter()
+            .map(|test| {
+                let id = test.number.map_or("-".to_owned(), |n| format!("#{n}"));
+                let feature = test
+                    .feature
+                    .as_ref()
+                    .map_or(String::new(), |f| format!("{f}: "));
+                let tags: String = test.tags.iter().map(|tag| format!(" @{tag}")).collect();
+                format!(
+                    "{id} {}:{}: {feature}{}{tags}\n",
+                    test.test.path.display(),
+                    test.test.line,
+                    test.test.name
+                )
+            })
+            .collect(),
+        ListFormat::Csv => {
+            let mut output = "id,file,line,feature,scenario,tags\n".to_owned();
+            for test in tests {
+                let record = ListRecord::from(test);
+                let fields = [
+                    record.id.map(|n| n.to_string()).unwrap_or_default(),
+                    record.file.display().to_string(),
+                    record.line.to_string(),
+                    record.feature.unwrap_or_default().to_owned(),
+                    record.scenario.to_owned(),
+                    record.tags.join(" "),
+                ];
+                let fields: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+                output.push_str(&fields.join(","));
+                output.push('\n');
+            }
+            output
+        }
+        ListFormat::Json => {
+            let records: Vec<_> = tests.iter().map(ListRecord::from).collect();
+            serde_json::to_string_pretty(&records).expect("records are always serializable")
+        }
+    }
+}
+
+/// The commits that changed test cases, one per line in text form.
+///
+/// ```text
+/// 3f2a9c1 2024-05-02 Alice: Raise the withdrawal limit [#1001, #1002]
+/// ```
+pub fn history(entries: &[HistoryEntry], format: ListFormat) -> String {
+    match format {
+        ListFormat::Text => entries
+            .iter()
+            .map(|entry| {
+                let numbers: Vec<_> = entry.numbers.iter().map(|n| format!("#{n}")).collect();
+                format!(
+                    "{:.7} {} {}: {} [{}]\n",
+                    entry.id.to_string(),
+                    format_date(entry.time),
+                    entry.author,
+                    entry.summary,
+                    numbers.join(", ")
+                )
+            })
+            .collect(),
+        ListFormat::Csv => {
+            let mut output = "commit,date,author,summary,ids\n".to_owned();
+            for record in entries.iter().map(HistoryRecord::from) {
+                let numbers: Vec<_> = record.ids.iter().map(u32::to_string).collect();
+                let fields = [
+                    record.commit,
+                    record.date,
+                    record.author.to_owned(),
+                    record.summary.to_owned(),
+                    numbers.join(" "),
+                ];
+                let fields: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+                output.push_str(&fields.join(","));
+                output.push('\n');
+            }
+            output
+        }
+        ListFormat::Json => {
+            let records: Vec<_> = entries.iter().map(HistoryRecord::from).collect();
+            serde_json::to_string_pretty(&records).expect("records are always serializable")
+        }
+    }
+}
+
+/// The commits whose trailer does not match their changes.
+///
+/// ```text
+/// 3f2a9c1 Raise the withdrawal limit: missing #1002, spurious #1005
+/// ```
+pub fn audit(audits: &[CommitAudit], format: ListFormat) -> String {
+    let references =
+        |numbers: &[u32]| -> Vec<String> { numbers.iter().map(|n| format!("#{n}")).collect() };
+
+    match format {
+        ListFormat::Text => audits
+            .iter()
+            .map(|audit| {
+                let mut problems = Vec::new();
+                if !audit.missing.is_empty() {
+                    problems.push(format!("missing {}", references(&audit.missing).join(", ")));
+                }
+                if !audit.spurious.is_empty() {
+                    problems.push(format!(
+                        "spurious {}",
+                        references(&audit.spurious).join(", ")
+                    ));
+                }
+                format!(
+                    "{:.7} {}: {}\n",
+                    audit.id.to_string(),
+                    audit.summary,
+                    problems.join(", ")
+                )
+            })
+            .collect(),
+        ListFormat::Csv => {
+            let mut output = "commit,summary,missing,spurious\n".to_owned();
+            for audit in audits {
+                let numbers = |numbers: &[u32]| -> String {
+                    let numbers: Vec<_> = numbers.iter().map(u32::to_string).collect();
+                    numbers.join(" ")
+                };
+                let fields = [
+                    audit.id.to_string(),
+                    audit.summary.clone(),
+                    numbers(&audit.missing),
+                    numbers(&audit.spurious),
+                ];
+                let fields: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+                output.push_str(&fields.join(","));
+                output.push('\n');
+            }
+            output
+        }
+        ListFormat::Json => {
+            let records: Vec<_> = audits.iter().map(AuditRecord::from).collect();
+            serde_json::to_string_pretty(&records).expect("records are always serializable")
+        }
+    }
+}
+
+/// Import formats of test management tools.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Comma separated values with a header line.
+    #[default]
+    Csv,
+    /// JSON for the bulk import of Cucumber tests into Xray.
+    Xray,
+    /// JSON with the cases in the fields of the TestRail API.
+    Testrail,
+}
+
+/// The changed test cases with their steps and last change.
+///
+/// For Xray, `key_prefix` turns the tracking number into the key of the Jira issue to update,
+/// e.g. `BANK-` for `BANK-1002`. Without it, the import creates new tests.
+pub fn export(tests: &[ExportedTest], format: ExportFormat, key_prefix: Option<&str>) -> String {
+    match format {
+        ExportFormat::Csv => {
+            let mut output =
+                "id,file,line,feature,scenario,steps,last_commit,last_modified\n".to_owned();
+            for exported in tests {
+                let test = &exported.test;
+                let fields = [
+                    test.number.map(|n| n.to_string()).unwrap_or_default(),
+                    test.test.path.display().to_string(),
+                    test.test.line.to_string(),
+                    test.feature.clone().unwrap_or_default(),
+                    test.test.name.clone(),
+                    test.steps.join("\n"),
+                    exported.last_commit.to_string(),
+                    format_date(exported.last_modified),
+                ];
+                let fields: Vec<_> = fields.iter().map(|f| csv_field(f)).collect();
+                output.push_str(&fields.join(","));
+                output.push('\n');
+            }
+            output
+        }
+        ExportFormat::Xray => {
+            let records: Vec<_> = tests
+                .iter()
+                .map(|exported| XrayTest::new(exported, key_prefix))
+                .collect();
+            serde_json::to_string_pretty(&records).expect("records are always serializable")
+        }
+        ExportFormat::Testrail => {
+            let cases: Vec<_> = tests.iter().map(TestRailCase::from).collect();
+            serde_json::to_string_pretty(&serde_json::json!({ "cases": cases }))
+                .expect("records are always serializable")
+        }
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+pub(crate) fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// All changes as a single JSON document.
+///
+/// ```json
+/// {"tests": [...], "untagged": [...], "warnings": [...]}
+/// ```
+pub fn json(changes: &ChangedTests) -> String {
+    let report = Report {
+        tests: changes.tests.iter().map(TestRecord::from).collect(),
+        untagged: changes.untagged.iter().map(ScenarioRecord::from).collect(),
+        warnings: changes.warnings.iter().map(WarningRecord::from).collect(),
+    };
+
+    serde_json::to_string_pretty(&report).expect("records are always serializable")
+}
+
+/// All changes as newline delimited JSON.
+///
+/// Every line is a record with a `type` of `test`, `untagged` or `warning`.
+pub fn ndjson(changes: &ChangedTests) -> String {
+    let tests = changes
+        .tests
+        .iter()
+        .map(|test| record("test", TestRecord::from(test)));
+    let untagged = changes
+        .untagged
+        .iter()
+        .map(|scenario| record("untagged", ScenarioRecord::from(scenario)));
+    let warnings = changes
+        .warnings
+        .iter()
+        .map(|warning| record("warning", WarningRecord::from(warning)));
+
+    tests
+        .chain(untagged)
+        .chain(warnings)
+        .map(|record| format!("{record}\n"))
+        .collect()
+}
+
+/// Why each number was reported, as a tree of numbers, test cases, reasons and changed lines.
+///
+/// ```text
+/// #1001
+/// └── features/withdraw.feature:8: Successful withdrawal
+///     └── Background changed
+///         ├── features/withdraw.feature:5 - Given the bank is open
+///         └── features/withdraw.feature:5 + Given the bank is open today
+/// ```
+pub fn explain(changes: &ChangedTests) -> String {
+    let mut roots: Vec<Node> = Vec::new();
+    let (tagged, untagged): (Vec<_>, Vec<_>) =
+        changes.tests.iter().partition(|test| test.number.is_some());
+    for test in tagged.into_iter().chain(untagged) {
+        let label = match test.number {
+            Some(number) => format!("#{number}"),
+            None => "Without tracking number".to_owned(),
+        };
+        let root = match roots.iter_mut().position(|node| node.label == label) {
+            Some(idx) => &mut roots[idx],
+            None => {
+                roots.push(Node::new(label));
+                roots.last_mut().unwrap()
+            }
+        };
+
+        let label = match &test.test {
+            Some(test) => test.to_string(),
+            None => "[fixtures] mapping of the configuration".to_owned(),
+        };
+        let test_node = match root.children.iter().position(|node| node.label == label) {
+            Some(idx) => &mut root.children[idx],
+            None => {
+                root.children.push(Node::new(label));
+                root.children.last_mut().unwrap()
+            }
+        };
+
+        let mut reason = Node::new(reason(&test.kind));
+        reason.children = test
+            .lines
+            .iter()
+            .map(|line| {
+                let sign = match line.version {
+                    Version::Old => '-',
+                    Version::New => '+',
+                };
+                Node::new(format!(
+                    "{}:{} {sign} {}",
+                    line.path.display(),
+                    line.line,
+                    line.text.trim()
+                ))
+            })
+            .collect();
+        test_node.children.push(reason);
+    }
+
+    let mut output = String::new();
+    for root in &roots {
+        root.render("", "", &mut output);
+    }
+    for warning in &changes.warnings {
+        output.push_str(&format!("{warning}\n"));
+    }
+    output
+}
+
+fn reason(kind: &ChangeKind) -> String {
+    match kind {
+        ChangeKind::TestCase => "Test case changed".to_owned(),
+        ChangeKind::Shared { name } => format!("{name} changed"),
+        ChangeKind::StepDefinition { function } => {
+            format!("Step definition {function} changed")
+        }
+        ChangeKind::Fixture { path } => format!("File {} changed", path.display()),
+    }
+}
+
+/// An entry of the tree printed by [`explain`].
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: String) -> Self {
+        Self {
+            label,
+            children: Vec::new(),
+        }
+    }
+
+    /// Append the node, with `prefix` in front of its own line and `indent` in front of its children.
+    fn render(&self, prefix: &str, indent: &str, output: &mut String) {
+        output.push_str(&format!("{prefix}{}\n", self.label));
+        for (idx, child) in self.children.iter().enumerate() {
+            let (prefix, next_indent) = if idx + 1 == self.children.len() {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            child.render(
+                &format!("{indent}{prefix}"),
+                &format!("{indent}{next_indent}"),
+                output,
+            );
+        }
+    }
+}
+
+/// Add the `type` field to a record.
+fn record(kind: &str, record: impl Serialize) -> Value {
+    let mut value = serde_json::to_value(record).expect("records are always serializable");
+    if let Value::Object(fields) = &mut value {
+        fields.insert("type".to_owned(), Value::from(kind));
+    }
+    value
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    tests: Vec<TestRecord<'a>>,
+    untagged: Vec<ScenarioRecord<'a>>,
+    warnings: Vec<WarningRecord<'a>>,
+}
+
+#[derive(Serialize)]
+struct TestRecord<'a> {
+    id: Option<u32>,
+    file: Option<&'a Path>,
+    line: Option<usize>,
+    scenario: Option<&'a str>,
+    /// `test_case`, `shared`, `step_definition` or `fixture`.
+    change: &'static str,
+    /// Name of the shared section or step definition, or path of the fixture.
+    cause: Option<String>,
+    lines: Vec<LineRange<'a>>,
+}
+
+impl<'a> From<&'a ChangedTest> for TestRecord<'a> {
+    fn from(test: &'a ChangedTest) -> Self {
+        let (change, cause) = match &test.kind {
+            ChangeKind::TestCase => ("test_case", None),
+            ChangeKind::Shared { name } => ("shared", Some(name.clone())),
+            ChangeKind::StepDefinition { function } => ("step_definition", Some(function.clone())),
+            ChangeKind::Fixture { path } => ("fixture", Some(path.display().to_string())),
+        };
+
+        Self {
+            id: test.number,
+            file: test.test.as_ref().map(|t| t.path.as_path()),
+            line: test.test.as_ref().map(|t| t.line),
+            scenario: test.test.as_ref().map(|t| t.name.as_str()),
+            change,
+            cause,
+            lines: line_ranges(&test.lines),
+        }
+    }
+}
+
+/// Consecutive changed lines of the same file and version, both ends inclusive.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct LineRange<'a> {
+    file: &'a Path,
+    /// `old` for removed lines, `new` for added lines.
+    version: &'static str,
+    start: u32,
+    end: u32,
+}
+
+fn line_ranges(lines: &[ChangedLine]) -> Vec<LineRange<'_>> {
+    let mut lines: Vec<_> = lines.iter().collect();
+    lines.sort_by_key(|line| (&line.path, line.version, line.line));
+
+    let mut ranges: Vec<LineRange> = Vec::new();
+    for line in lines {
+        let version = version_name(line.version);
+        match ranges.last_mut() {
+            Some(range)
+                if range.file == line.path
+                    && range.version == version
+                    && range.end + 1 == line.line =>
+            {
+                range.end = line.line;
+            }
+            _ => ranges.push(LineRange {
+                file: &line.path,
+                version,
+                start: line.line,
+                end: line.line,
+            }),
+        }
+    }
+    ranges
+}
+
+fn version_name(version: Version) -> &'static str {
+    match version {
+        Version::Old => "old",
+        Version::New => "new",
+    }
+}
+
+#[derive(Serialize)]
+struct ScenarioRecord<'a> {
+    file: &'a Path,
+    line: usize,
+    scenario: &'a str,
+}
+
+impl<'a> From<&'a TestCase> for ScenarioRecord<'a> {
+    fn from(test: &'a TestCase) -> Self {
+        Self {
+            file: &test.path,
+            line: test.line,
+            scenario: &test.name,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ListRecord<'a> {
+    id: Option<u32>,
+    file: &'a Path,
+    line: usize,
+    feature: Option<&'a str>,
+    scenario: &'a str,
+    tags: &'a [String],
+}
+
+impl<'a> From<&'a ScannedTest> for ListRecord<'a> {
+    fn from(test: &'a ScannedTest) -> Self {
+        Self {
+            id: test.number,
+            file: &test.test.path,
+            line: test.test.line,
+            feature: test.feature.as_deref(),
+            scenario: &test.test.name,
+            tags: &test.tags,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HistoryRecord<'a> {
+    commit: String,
+    /// `YYYY-MM-DD`
+    date: String,
+    author: &'a str,
+    summary: &'a str,
+    ids: &'a [u32],
+}
+
+impl<'a> From<&'a HistoryEntry> for HistoryRecord<'a> {
+    fn from(entry: &'a HistoryEntry) -> Self {
+        Self {
+            commit: entry.id.to_string(),
+            date: format_date(entry.time),
+            author: &entry.author,
+            summary: &entry.summary,
+            ids: &entry.numbers,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    commit: String,
+    summary: &'a str,
+    missing: &'a [u32],
+    spurious: &'a [u32],
+}
+
+impl<'a> From<&'a CommitAudit> for AuditRecord<'a> {
+    fn from(audit: &'a CommitAudit) -> Self {
+        Self {
+            commit: audit.id.to_string(),
+            summary: &audit.summary,
+            missing: &audit.missing,
+            spurious: &audit.spurious,
+        }
+    }
+}
+
+/// A test of the Xray JSON bulk import.
+#[derive(Serialize)]
+struct XrayTest<'a> {
+    testtype: &'static str,
+    /// Key of the existing issue to update.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update_key: Option<String>,
+    fields: XrayFields<'a>,
+    gherkin_def: String,
+    xray_test_repository_folder: String,
+}
+
+#[derive(Serialize)]
+struct XrayFields<'a> {
+    summary: &'a str,
+    description: String,
+    labels: &'a [String],
+}
+
+impl<'a> XrayTest<'a> {
+    fn new(exported: &'a ExportedTest, key_prefix: Option<&str>) -> Self {
+        let test = &exported.test;
+        let folder = test.test.path.parent().unwrap_or(Path::new(""));
+        Self {
+            testtype: "Cucumber",
+            update_key: key_prefix
+                .zip(test.number)
+                .map(|(prefix, number)| format!("{prefix}{number}")),
+            fields: XrayFields {
+                summary: &test.test.name,
+                description: format!(
+                    "{}:{}, last changed in {:.7} on {}",
+                    test.test.path.display(),
+                    test.test.line,
+                    exported.last_commit.to_string(),
+                    format_date(exported.last_modified)
+                ),
+                labels: &test.tags,
+            },
+            gherkin_def: test.steps.join("\n"),
+            xray_test_repository_folder: folder.display().to_string(),
+        }
+    }
+}
+
+/// A case with the fields of the TestRail API, `custom_*` fields have to exist in TestRail.
+#[derive(Serialize)]
+struct TestRailCase<'a> {
+    /// Not the `id` of the case, which TestRail assigns itself.
+    custom_tracking_number: Option<u32>,
+    title: &'a str,
+    /// Name of the feature.
+    section: Option<&'a str>,
+    refs: String,
+    custom_steps_separated: Vec<TestRailStep<'a>>,
+    custom_last_commit: String,
+    /// `YYYY-MM-DD`
+    custom_last_modified: String,
+}
+
+#[derive(Serialize)]
+struct TestRailStep<'a> {
+    content: &'a str,
+    expected: &'static str,
+}
+
+impl<'a> From<&'a ExportedTest> for TestRailCase<'a> {
+    fn from(exported: &'a ExportedTest) -> Self {
+        let test = &exported.test;
+        Self {
+            custom_tracking_number: test.number,
+            title: &test.test.name,
+            section: test.feature.as_deref(),
+            refs: test.test.path.display().to_string(),
+            custom_steps_separated: test
+                .steps
+                .iter()
+                .map(|step| TestRailStep {
+                    content: step,
+                    expected: "",
+                })
+                .collect(),
+            custom_last_commit: exported.last_commit.to_string(),
+            custom_last_modified: format_date(exported.last_modified),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "warning", rename_all = "snake_case")]
+enum WarningRecord<'a> {
+    ParseError {
+        file: &'a Path,
+        version: &'static str,
+        format: &'a str,
+        message: &'a str,
+    },
+    InvalidStepPattern {
+        file: &'a Path,
+        function: &'a str,
+        message: &'a str,
+    },
+}
+
+impl<'a> From<&'a Warning> for WarningRecord<'a> {
+    fn from(warning: &'a Warning) -> Self {
+        match warning {
+            Warning::Parse(error) => Self::ParseError {
+                file: &error.path,
+                version: version_name(error.version),
+                format: &error.format,
+                message: &error.error.message,
+            },
+            Warning::InvalidStepPattern {
+                path,
+                function,
+                message,
+            } => Self::InvalidStepPattern {
+                file: path,
+                function,
+                message,
+            },
+        }
+    }
+}
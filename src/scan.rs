@@ -0,0 +1,166 @@
+//! All test cases of a revision, not only the changed ones.
+//!
+//! Used by the commands that look at the whole repository, like `doctor`, `lint` and `list`.
+
+use std::path::{Path, PathBuf};
+
+use git2::{ObjectType, Oid, Repository, Tree, TreeWalkMode, TreeWalkResult};
+
+use crate::{
+    formats::{TestFile, TestItem},
+    ExtractNumberError, FileParseError, Operation, Options, PathFilter, TestCase, Version, Warning,
+};
+
+/// The version of the repository to scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Revision {
+    /// The staged files, i.e. what would be committed.
+    Index,
+    /// A commit, given like on the git command line, e.g. `HEAD` or `v1.0`.
+    Commit(String),
+}
+
+/// The test cases of all included files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScannedTests {
+    /// Every test case, sorted by path and line.
+    pub tests: Vec<ScannedTest>,
+    /// Number of files that were handled by a format, including those that failed to parse.
+    pub files: usize,
+    /// Files that could not be parsed.
+    pub warnings: Vec<Warning>,
+}
+
+/// A test case found while scanning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScannedTest {
+    /// Tracking number, `None` if the test case does not have one.
+    pub number: Option<u32>,
+    pub test: TestCase,
+    /// Name of the feature, only known for Gherkin.
+    pub feature: Option<String>,
+    /// All tags of the test case, including the tracking number.
+    pub tags: Vec<String>,
+    /// Lines of the test case after the first one, e.g. the steps of a scenario.
+    ///
+    /// Lines are trimmed, empty lines and comments are left out.
+    pub steps: Vec<String>,
+}
+
+/// Extract the test cases of every included file in a revision.
+pub fn scan(
+    repo: &Repository,
+    revision: &Revision,
+    opts: &Options,
+) -> Result<ScannedTests, ExtractNumberError> {
+    let filter = PathFilter::new(opts)?;
+    let version = match revision {
+        Revision::Index => Version::New,
+        Revision::Commit(_) => Version::Old,
+    };
+
+    let mut scanned = ScannedTests::default();
+    for (path, id) in files(repo, revision)? {
+        if !filter.matches(&path) {
+            continue;
+        }
+        let Some(format) = opts.formats.iter().find(|f| f.handles(&path)) else {
+            continue;
+        };
+        scanned.files += 1;
+
+        let blob = repo
+            .find_blob(id)
+            .map_err(ExtractNumberError::git(Operation::ReadBlob, Some(&path)))?;
+        let text = String::from_utf8_lossy(blob.content());
+
+        match format.parse(&text) {
+            Ok(file) => scanned.tests.extend(
+                file.items
+                    .iter()
+                    .map(|item| ScannedTest::new(&path, &text, &file, item, opts)),
+            ),
+            Err(error) => scanned.warnings.push(Warning::Parse(FileParseError {
+                path,
+                version,
+                format: format.name().to_owned(),
+                error,
+            })),
+        }
+    }
+
+    scanned
+        .tests
+        .sort_by(|a, b| (&a.test.path, a.test.line).cmp(&(&b.test.path, b.test.line)));
+    Ok(scanned)
+}
+
+/// Paths and blob ids of all files in the revision.
+fn files(
+    repo: &Repository,
+    revision: &Revision,
+) -> Result<Vec<(PathBuf, Oid)>, ExtractNumberError> {
+    match revision {
+        Revision::Index => index_files(repo),
+        Revision::Commit(spec) => {
+            let tree = repo
+                .revparse_single(spec)
+                .and_then(|object| object.peel_to_tree())
+                .map_err(ExtractNumberError::git(Operation::ResolveRevision, None))?;
+            tree_files(&tree)
+        }
+    }
+}
+
+/// Path and blob of every staged file.
+pub(crate) fn index_files(repo: &Repository) -> Result<Vec<(PathBuf, Oid)>, ExtractNumberError> {
+    let index = repo
+        .index()
+        .map_err(ExtractNumberError::git(Operation::ReadIndex, None))?;
+    Ok(index
+        .iter()
+        .map(|entry| (path(&entry.path), entry.id))
+        .collect())
+}
+
+/// Path and blob of every file in a tree, including subdirectories.
+pub(crate) fn tree_files(tree: &Tree) -> Result<Vec<(PathBuf, Oid)>, ExtractNumberError> {
+    let mut files = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(ObjectType::Blob) {
+            let mut name = dir.as_bytes().to_vec();
+            name.extend_from_slice(entry.name_bytes());
+            files.push((path(&name), entry.id()));
+        }
+        TreeWalkResult::Ok
+    })
+    .map_err(ExtractNumberError::git(Operation::ReadTree, None))?;
+
+    Ok(files)
+}
+
+fn path(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+impl ScannedTest {
+    fn new(path: &Path, text: &str, file: &TestFile, item: &TestItem, opts: &Options) -> Self {
+        let steps = text
+            .get(item.span.clone())
+            .unwrap_or_default()
+            .lines()
+            .skip(1)
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(ToOwned::to_owned)
+            .collect();
+
+        Self {
+            number: item.number(&opts.test_prefix),
+            test: TestCase::new(path, item),
+            feature: file.feature.clone(),
+            tags: item.tags.clone(),
+            steps,
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Impact analysis for changed step definitions.
+//!
+//! A step definition is the code that implements a Gherkin step,
+//! e.g. a Rust function with `#[given(expr = "Alice has {float} in their account")]`
+//! or a Python function decorated with `@given("Alice has {amount} in their account")`.
+//! When a step definition changes, every scenario using a matching step is changed as well.
+
+use std::{collections::BTreeMap, ops::Range, path::PathBuf};
+
+use gherkin::{Scenario, Step, StepType};
+use git2::Repository;
+use regex::Regex;
+
+use crate::{
+    calculate_line_spans, changed_lines, changed_scenario, formats, line_to_byte_offset,
+    new_features, parse_new_feature, read_version, ChangeKind, ChangedLine, ChangedTest,
+    ExtractNumberError, Options, PathFilter, SpanExt, Version, Versions, Warning,
+};
+
+/// A function implementing a Gherkin step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepDefinition {
+    /// Name of the function.
+    pub function: String,
+    /// Line where the definition starts, 1 based.
+    pub line: usize,
+    /// Byte range of the definition in the file, including attributes and body.
+    pub span: Range<usize>,
+    /// Type of steps this definition applies to, `None` for all types.
+    pub step_type: Option<StepType>,
+    pub pattern: StepPattern,
+}
+
+/// The text a step definition matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepPattern {
+    /// The step text has to be exactly this string.
+    Literal(String),
+    /// A [cucumber expression](https://github.com/cucumber/cucumber-expressions),
+    /// like `Alice has {float} in their account`.
+    Expression(String),
+    /// A regular expression.
+    Regex(String),
+    /// A pattern of the Python `parse` library, like `Alice has {amount:f} in their account`.
+    Parse(String),
+}
+
+impl StepPattern {
+    /// Convert the pattern into a regular expression matching the step text.
+    pub fn to_regex(&self) -> Result<Regex, regex::Error> {
+        match self {
+            StepPattern::Literal(text) => Regex::new(&format!("^{}$", regex::escape(text))),
+            StepPattern::Expression(expr) => Regex::new(&expression_to_regex(expr)),
+            StepPattern::Regex(regex) => Regex::new(regex),
+            StepPattern::Parse(pattern) => Regex::new(&parse_pattern_to_regex(pattern)),
+        }
+    }
+}
+
+/// A changed step definition together with its compiled pattern.
+struct Matcher {
+    function: String,
+    step_type: Option<StepType>,
+    regex: Regex,
+    /// The changed lines inside the definition.
+    lines: Vec<ChangedLine>,
+}
+
+impl Matcher {
+    fn matches(&self, step: &Step, text: &str) -> bool {
+        self.step_type.is_none_or(|ty| ty == step.ty) && self.regex.is_match(text)
+    }
+}
+
+/// Scenarios using step definitions changed in the diff.
+pub(crate) fn changed_step_usages(
+    repo: &Repository,
+    versions: &Versions,
+    diff: &git2::Diff,
+    opts: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<ChangedTest>, ExtractNumberError> {
+    let mut tests = Vec::new();
+
+    if opts.step_definitions.is_empty() {
+        return Ok(tests);
+    }
+
+    let step_filter = PathFilter::from_patterns(&opts.step_definitions, &opts.exclude)?;
+
+    // Each version of a definition file is read and parsed once, for all of its changed lines
+    let mut files: BTreeMap<(PathBuf, Version), Vec<ChangedLine>> = BTreeMap::new();
+    for change in changed_lines(diff, &step_filter) {
+        files
+            .entry((change.path.clone(), change.version))
+            .or_default()
+            .push(change);
+    }
+
+    let mut matchers = Vec::new();
+    for ((path, _), changes) in files {
+        let text = read_version(repo, versions, &changes[0])?;
+        let offsets = calculate_line_spans(&text);
+
+        for definition in formats::step_definitions(&path, &text) {
+            let lines: Vec<_> = changes
+                .iter()
+                .filter(|change| {
+                    line_to_byte_offset(&offsets, change.line)
+                        .is_some_and(|line| definition.span.intersects(&line))
+                })
+                .cloned()
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
+
+            match definition.pattern.to_regex() {
+                Ok(regex) => matchers.push(Matcher {
+                    function: definition.function,
+                    step_type: definition.step_type,
+                    regex,
+                    lines,
+                }),
+                Err(err) => warnings.push(Warning::InvalidStepPattern {
+                    path: path.clone(),
+                    function: definition.function,
+                    message: err.to_string(),
+                }),
+            }
+        }
+    }
+
+    if matchers.is_empty() {
+        return Ok(tests);
+    }
+
+    for (path, text) in new_features(repo, versions, opts)? {
+        let Some(feature) = parse_new_feature(&path, &text, opts, warnings) else {
+            continue;
+        };
+
+        for (backgrounds, scenarios) in formats::scenario_groups(&feature) {
+            for matcher in &matchers {
+                let background_uses = backgrounds.iter().any(|steps| uses(steps, &[], matcher));
+                for scenario in scenarios {
+                    if !background_uses && !uses(&scenario.steps, &outline_rows(scenario), matcher)
+                    {
+                        continue;
+                    }
+
+                    let kind = ChangeKind::StepDefinition {
+                        function: matcher.function.clone(),
+                    };
+                    tests.push(changed_scenario(
+                        &path,
+                        scenario,
+                        opts,
+                        kind,
+                        matcher.lines.clone(),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(tests)
+}
+
+/// Values of each row of the examples of a scenario outline, keyed by column name.
+fn outline_rows(scenario: &Scenario) -> Vec<Vec<(String, String)>> {
+    scenario
+        .examples
+        .iter()
+        .filter_map(|examples| examples.table.as_ref())
+        .flat_map(|table| {
+            let (header, rows) = table.rows.split_first()?;
+            Some(rows.iter().map(move |row| {
+                header
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().cloned())
+                    .collect::<Vec<_>>()
+            }))
+        })
+        .flatten()
+        .collect()
+}
+
+/// Whether any of the steps matches the step definition.
+///
+/// Steps of scenario outlines are checked with the values of every example row.
+fn uses(steps: &[Step], rows: &[Vec<(String, String)>], matcher: &Matcher) -> bool {
+    steps.iter().any(|step| {
+        let mut texts = vec![step.value.clone()];
+        texts.extend(rows.iter().map(|row| {
+            row.iter().fold(step.value.clone(), |text, (name, value)| {
+                text.replace(&format!("<{name}>"), value)
+            })
+        }));
+
+        texts.iter().any(|text| matcher.matches(step, text))
+    })
+}
+
+/// Translate a cucumber expression into an anchored regular expression.
+fn expression_to_regex(expr: &str) -> String {
+    let mut regex = String::from("^");
+
+    for (idx, word) in split_keep_whitespace(expr).into_iter().enumerate() {
+        if idx % 2 == 1 {
+            // whitespace between words
+            regex.push_str(&regex::escape(word));
+        } else if word.contains('/') && !word.contains(['{', '(']) {
+            let alternatives: Vec<_> = split_unescaped(word, '/')
+                .into_iter()
+                .map(|alt| regex::escape(&unescape(&alt)))
+                .collect();
+            regex.push_str(&format!("(?:{})", alternatives.join("|")));
+        } else {
+            regex.push_str(&expression_word_to_regex(word));
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+/// Translate a part of a cucumber expression without whitespace or alternatives.
+fn expression_word_to_regex(word: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    regex.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            '{' => {
+                let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                regex.push_str(parameter_regex(&name));
+            }
+            '(' => {
+                let optional: String = chars.by_ref().take_while(|&c| c != ')').collect();
+                regex.push_str(&format!("(?:{})?", regex::escape(&optional)));
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex
+}
+
+/// Regular expression for a parameter type of a cucumber expression.
+fn parameter_regex(name: &str) -> &'static str {
+    match name {
+        "int" | "byte" | "short" | "long" | "biginteger" => r"(-?\d+)",
+        "float" | "double" | "bigdecimal" => r"(-?\d*(?:[.,]\d+)?(?:[eE]-?\d+)?)",
+        "word" => r"([^\s]+)",
+        "string" => r#"("[^"]*"|'[^']*')"#,
+        // anonymous and custom parameter types
+        _ => r"(.*)",
+    }
+}
+
+/// Translate a pattern of the Python `parse` library into an anchored regular expression.
+fn parse_pattern_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                regex.push_str(r"\{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                regex.push_str(r"\}");
+            }
+            '{' => {
+                chars.by_ref().take_while(|&c| c != '}').for_each(drop);
+                regex.push_str("(.+?)");
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// Split into words and the whitespace between them, words are at even indices.
+fn split_keep_whitespace(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut in_whitespace = false;
+    for (idx, c) in text.char_indices() {
+        if c.is_whitespace() != in_whitespace {
+            parts.push(&text[start..idx]);
+            start = idx;
+            in_whitespace = !in_whitespace;
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Split at `separator`, unless it is escaped with a backslash.
+fn split_unescaped(text: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            parts.last_mut().unwrap().push(c);
+            parts.last_mut().unwrap().extend(chars.next());
+        } else if c == separator {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+    parts
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            result.extend(chars.next());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
@@ -0,0 +1,259 @@
+mod common;
+
+use std::fs;
+
+use common::*;
+use show_changed_tests::{
+    assign::{assign_numbers, insert_tags, AssignError, NumberRange, NumberSource},
+    config::Config,
+    Options,
+};
+
+const FEATURE: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+
+  Scenario: Untagged withdrawal
+    -When Alice tries to withdraw 200.00
+    +When Alice tries to withdraw 250.00
++
++  @smoke
++  Scenario: New withdrawal
++    When Alice tries to withdraw 300.00
++
++  Rule: Overdraft
++
++    Scenario Outline: New overdraft
++      When Alice tries to withdraw <amount>
++
++      Examples:
++        | amount |
++        | 100    |
+";
+
+const TAGGED: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+
+  Scenario: Untagged withdrawal
+    When Alice tries to withdraw 250.00
+
+  @smoke
+  @tc:1002
+  Scenario: New withdrawal
+    When Alice tries to withdraw 300.00
+
+  Rule: Overdraft
+
+    @tc:1003
+    Scenario Outline: New overdraft
+      When Alice tries to withdraw <amount>
+
+      Examples:
+        | amount |
+        | 100    |
+";
+
+#[test]
+fn tags_are_inserted_above_the_keyword() {
+    let text = "Feature: F\r\n\r\n\t@slow\r\n\tScenario: S\r\n\t\tGiven x\r\n";
+
+    assert_eq!(
+        insert_tags(text, &[(4, "tc:7".into())]),
+        "Feature: F\r\n\r\n\t@slow\r\n\t@tc:7\r\n\tScenario: S\r\n\t\tGiven x\r\n"
+    );
+}
+
+#[test]
+fn new_scenarios_get_the_next_numbers() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+
+    let assignments = assign_numbers(
+        repo.git_repo(),
+        &Options::default(),
+        &NumberSource::Next,
+        false,
+    )
+    .unwrap();
+
+    let assigned: Vec<_> = assignments
+        .iter()
+        .map(|a| (a.test.name.as_str(), a.test.line, a.number))
+        .collect();
+    assert_eq!(
+        assigned,
+        [("New withdrawal", 12, 1002), ("New overdraft", 17, 1003)]
+    );
+    let path = repo.root().join("withdraw.feature");
+    assert_eq!(fs::read_to_string(path).unwrap(), TAGGED);
+
+    // The tagged version is staged, so there is nothing left to assign
+    let again = assign_numbers(
+        repo.git_repo(),
+        &Options::default(),
+        &NumberSource::Next,
+        false,
+    )
+    .unwrap();
+    assert_eq!(again, []);
+}
+
+#[test]
+fn dry_run_does_not_change_files() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    let path = repo.root().join("withdraw.feature");
+    let before = fs::read_to_string(&path).unwrap();
+
+    let assignments = assign_numbers(
+        repo.git_repo(),
+        &Options::default(),
+        &NumberSource::Next,
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(assignments.len(), 2);
+    assert_eq!(fs::read_to_string(&path).unwrap(), before);
+}
+
+#[test]
+fn reserved_range_skips_used_numbers() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+
+    let source = NumberSource::Range(NumberRange::new(1000, 1003));
+    let assignments = assign_numbers(repo.git_repo(), &Options::default(), &source, true).unwrap();
+
+    let numbers: Vec<_> = assignments.iter().map(|a| a.number).collect();
+    assert_eq!(numbers, [1000, 1002]);
+
+    let source = NumberSource::Range(NumberRange::new(1000, 1001));
+    let result = assign_numbers(repo.git_repo(), &Options::default(), &source, true);
+    assert!(matches!(
+        result,
+        Err(AssignError::Exhausted {
+            needed: 2,
+            available: 1
+        })
+    ));
+}
+
+#[test]
+fn allocation_file_is_consumed() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file(
+        ".tc-numbers",
+        "# Reserved for Alice\n1001\n2000-2001\n3000-3009\n",
+    );
+
+    let source = NumberSource::File(".tc-numbers".into());
+    let assignments = assign_numbers(repo.git_repo(), &Options::default(), &source, false).unwrap();
+
+    let numbers: Vec<_> = assignments.iter().map(|a| a.number).collect();
+    assert_eq!(numbers, [2000, 2001]);
+    assert_eq!(
+        fs::read_to_string(repo.root().join(".tc-numbers")).unwrap(),
+        "# Reserved for Alice\n3000-3009\n"
+    );
+}
+
+#[test]
+fn allocation_file_keeps_comments_and_empty_lines() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file(
+        ".tc-numbers",
+        "# Reserved for Alice\n\n  # Sprint 12\n2000-2002\n\n# Sprint 13\n3000-3009\n",
+    );
+
+    let source = NumberSource::File(".tc-numbers".into());
+    assign_numbers(repo.git_repo(), &Options::default(), &source, false).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(repo.root().join(".tc-numbers")).unwrap(),
+        "# Reserved for Alice\n\n  # Sprint 12\n2002\n\n# Sprint 13\n3000-3009\n"
+    );
+}
+
+#[test]
+fn allocation_file_is_unchanged_if_staging_fails() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file(".tc-numbers", "2000-2009\n");
+    // Another git process holds the index
+    repo.write_file(".git/index.lock", "");
+
+    let source = NumberSource::File(".tc-numbers".into());
+    let result = assign_numbers(repo.git_repo(), &Options::default(), &source, false);
+
+    assert!(matches!(result, Err(AssignError::Stage { .. })));
+    assert_eq!(
+        fs::read_to_string(repo.root().join(".tc-numbers")).unwrap(),
+        "2000-2009\n"
+    );
+}
+
+#[test]
+fn unstaged_changes_are_not_overwritten() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file("withdraw.feature", "Feature: Changed\n");
+
+    let result = assign_numbers(
+        repo.git_repo(),
+        &Options::default(),
+        &NumberSource::Next,
+        false,
+    );
+
+    assert!(matches!(result, Err(AssignError::UnstagedChanges(_))));
+    assert_eq!(
+        fs::read_to_string(repo.root().join("withdraw.feature")).unwrap(),
+        "Feature: Changed\n"
+    );
+}
+
+#[test]
+fn number_source_is_configured() {
+    assert_eq!(
+        Config::default().number_source().unwrap(),
+        NumberSource::Next
+    );
+    assert_eq!(
+        Config::from_toml("allocation-range = \"5000-5999\"")
+            .unwrap()
+            .number_source()
+            .unwrap(),
+        NumberSource::Range(NumberRange::new(5000, 5999))
+    );
+    assert_eq!(
+        Config::from_toml("allocation-file = \"numbers.txt\"")
+            .unwrap()
+            .number_source()
+            .unwrap(),
+        NumberSource::File("numbers.txt".into())
+    );
+    assert!(Config::from_toml("allocation = \"range\"")
+        .unwrap()
+        .number_source()
+        .is_err());
+    assert!(Config::from_toml("allocation-range = \"5999-5000\"").is_err());
+
+    let repo = TestRepository::new();
+    repo.git(&["config", "showChangedTests.allocation", "RANGE"]);
+    repo.git(&["config", "showChangedTests.allocationRange", "10-20"]);
+    let config = Config::load(repo.git_repo()).unwrap();
+    assert_eq!(
+        config.number_source().unwrap(),
+        NumberSource::Range(NumberRange::new(10, 20))
+    );
+}
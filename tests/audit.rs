@@ -0,0 +1,98 @@
+mod common;
+
+use common::*;
+use show_changed_tests::{
+    audit::{audit, CommitAudit},
+    history::NumberCache,
+    Options,
+};
+
+const FEATURE: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+
+  @tc:1002
+  Scenario: Declined withdrawal
+    When Hamza tries to withdraw 200.00
+";
+
+fn run_audit(repo: &TestRepository, range: &str) -> Vec<CommitAudit> {
+    audit(
+        repo.git_repo(),
+        range,
+        "Tests",
+        &Options::default(),
+        &mut NumberCache::in_memory(),
+    )
+    .unwrap()
+}
+
+fn problems(audits: &[CommitAudit]) -> Vec<(&str, &[u32], &[u32])> {
+    audits
+        .iter()
+        .map(|a| {
+            (
+                a.summary.as_str(),
+                a.missing.as_slice(),
+                a.spurious.as_slice(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn matching_trailers_pass() {
+    let repo = TestRepository::new();
+    repo.commit_file(
+        "withdraw.feature",
+        FEATURE,
+        "Add withdrawal\n\nTests: #1001, #1002",
+    );
+    repo.commit_file("README.md", "No tests\n", "Add readme");
+
+    assert_eq!(run_audit(&repo, "HEAD"), []);
+}
+
+#[test]
+fn missing_and_spurious_numbers_are_reported() {
+    let repo = TestRepository::new();
+    repo.commit_file(
+        "withdraw.feature",
+        FEATURE,
+        "Add withdrawal\n\nTests: #1001",
+    );
+    repo.commit_file(
+        "withdraw.feature",
+        &FEATURE.replace("Hamza tries", "Hamza tries again"),
+        "Retry declined withdrawal\n\nTests: #1001",
+    );
+
+    assert_eq!(
+        problems(&run_audit(&repo, "HEAD")),
+        [
+            ("Retry declined withdrawal", &[1002][..], &[1001][..]),
+            ("Add withdrawal", &[1002][..], &[][..]),
+        ]
+    );
+}
+
+#[test]
+fn only_the_range_is_checked() {
+    let repo = TestRepository::new();
+    repo.commit_file(
+        "withdraw.feature",
+        FEATURE,
+        "Add withdrawal without trailer",
+    );
+    repo.commit_file(
+        "withdraw.feature",
+        &FEATURE.replace("Alice tries", "Alice tries again"),
+        "Retry withdrawal\n\ntests: #1001",
+    );
+
+    assert_eq!(run_audit(&repo, "HEAD~1..HEAD"), []);
+    assert_eq!(run_audit(&repo, "HEAD").len(), 1);
+}
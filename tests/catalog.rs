@@ -0,0 +1,195 @@
+mod common;
+
+use std::{
+    path::Path,
+    process::{Command, Output},
+};
+
+use common::*;
+use show_changed_tests::{
+    catalog::{Catalog, CatalogError, Problem, Violation},
+    changed_tests,
+    lint::{check_catalog, Issue},
+    scan::{scan, Revision},
+    Options, TestCase,
+};
+
+const FEATURE: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    -When Alice tries to withdraw 200.00
+    +When Alice tries to withdraw 250.00
+
+  @tc:10002
+  Scenario: Declined withdrawal
+    -When Hamza tries to withdraw 200.00
+    +When Hamza tries to withdraw 250.00
+
+  @tc:1003
+  Scenario: Withdrawal at the counter
+    -When Alice withdraws 200.00 at the counter
+    +When Alice withdraws 250.00 at the counter
+";
+
+const CATALOG: &str = "id,title,status
+1001,Successful withdrawal,active
+1002,Declined withdrawal,active
+1003,Withdrawal at the counter,retired
+";
+
+fn run_hook(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_show-changed-tests"))
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_CEILING_DIRECTORIES", dir.parent().unwrap())
+        .env_remove("GIT_DIR")
+        .output()
+        .unwrap()
+}
+
+fn test_case(line: usize, name: &str) -> TestCase {
+    TestCase {
+        path: "withdraw.feature".into(),
+        line,
+        name: name.into(),
+    }
+}
+
+#[test]
+fn csv_catalog_uses_id_and_status_columns() {
+    let catalog = Catalog::from_csv(CATALOG).unwrap();
+
+    assert_eq!(catalog.check(1001), None);
+    assert_eq!(catalog.check(1003), Some(Problem::Retired));
+    assert_eq!(catalog.check(10002), Some(Problem::Unknown));
+
+    let catalog = Catalog::from_csv("#1001\n\n1002, Retired\n").unwrap();
+    assert_eq!(catalog.check(1001), None);
+    assert_eq!(catalog.check(1002), Some(Problem::Retired));
+
+    assert_eq!(Catalog::from_csv("id\n1001\ntc-1002\n"), Err(3));
+}
+
+#[test]
+fn json_catalog_contains_numbers_or_objects() {
+    let catalog = Catalog::from_json("[1001, 1002]").unwrap();
+    assert_eq!(catalog.check(1002), None);
+    assert_eq!(catalog.check(1003), Some(Problem::Unknown));
+
+    let catalog =
+        Catalog::from_json(r#"[{"id": 1001}, {"id": 1002, "status": "retired"}]"#).unwrap();
+    assert_eq!(catalog.check(1001), None);
+    assert_eq!(catalog.check(1002), Some(Problem::Retired));
+}
+
+#[test]
+fn missing_catalog_is_an_error() {
+    assert!(matches!(
+        Catalog::load(Path::new("does/not/exist.csv")),
+        Err(CatalogError::Io { .. })
+    ));
+}
+
+#[test]
+fn changed_tests_with_unknown_or_retired_numbers_are_violations() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    let changes = changed_tests(repo.git_repo(), &Options::default()).unwrap();
+
+    let violations = Catalog::from_csv(CATALOG).unwrap().violations(&changes);
+
+    assert_eq!(
+        violations,
+        [
+            Violation {
+                number: 1003,
+                test: Some(test_case(13, "Withdrawal at the counter")),
+                problem: Problem::Retired,
+            },
+            Violation {
+                number: 10002,
+                test: Some(test_case(9, "Declined withdrawal")),
+                problem: Problem::Unknown,
+            },
+        ]
+    );
+}
+
+#[test]
+fn lint_checks_all_numbers_against_catalog() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    let opts = Options::default();
+    let scanned = scan(repo.git_repo(), &Revision::Index, &opts).unwrap();
+
+    let issues = check_catalog(
+        &scanned,
+        &opts.test_prefix,
+        &Catalog::from_csv(CATALOG).unwrap(),
+    );
+
+    assert_eq!(
+        issues,
+        [
+            Issue::Catalog {
+                test: test_case(9, "Declined withdrawal"),
+                number: 10002,
+                problem: Problem::Unknown,
+            },
+            Issue::Catalog {
+                test: test_case(13, "Withdrawal at the counter"),
+                number: 1003,
+                problem: Problem::Retired,
+            },
+        ]
+    );
+}
+
+#[test]
+fn hook_warns_about_unknown_numbers_by_default() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file("catalog.csv", CATALOG);
+    repo.write_file(".show-changed-tests.toml", "catalog = \"catalog.csv\"\n");
+
+    let output = run_hook(repo.root(), &[]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "withdraw.feature:9: Declined withdrawal: tracking number 10002 is not in the catalog"
+        ),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("tracking number 1003 is retired"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn hook_and_lint_fail_on_unknown_numbers_if_configured() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file("catalog.json", "[1001, 1002, 1003]");
+    let args = ["--catalog", "catalog.json", "--unknown-numbers", "fail"];
+
+    let hook = run_hook(repo.root(), &args);
+    assert_eq!(hook.status.code(), Some(1));
+
+    let mut lint_args = vec!["lint"];
+    lint_args.extend(args);
+    let lint = run_hook(repo.root(), &lint_args);
+    assert_eq!(lint.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&lint.stdout),
+        "withdraw.feature:9: Declined withdrawal: tracking number 10002 is not in the catalog\n"
+    );
+
+    // Only a warning by default
+    let lint = run_hook(repo.root(), &["lint", "--catalog", "catalog.json"]);
+    assert!(lint.status.success());
+}
@@ -1,6 +1,14 @@
 //! Common utility functions for tests
+// Each test binary only uses a subset of the helpers.
+#![allow(dead_code)]
 
-use std::{fs::File, io::Write, process::Command};
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
 use git2::Repository;
 use tempfile::TempDir;
@@ -14,6 +22,10 @@ impl TestRepository {
     pub fn new() -> Self {
         let location = TempDir::with_prefix("scenario-number-test-").unwrap();
         let git_repo = Repository::init(&location).unwrap();
+        // libgit2 only reads the identity from the config, e.g. for notes
+        let mut config = git_repo.config().unwrap();
+        config.set_str("user.name", "t").unwrap();
+        config.set_str("user.email", "t@example.com").unwrap();
 
         Self { git_repo, location }
     }
@@ -36,26 +48,50 @@ impl TestRepository {
             .filter_map(|line| filter_diff(line, DiffKind::New))
             .map(|line| format!("{line}\n"))
             .collect();
-        let mut file = File::create(self.location.path().join(name)).unwrap();
+        let mut file = File::create(self.path(name)).unwrap();
         file.write_all(lines_before.as_bytes()).unwrap();
 
         self.git(&["add", name]);
         self.git(&["commit", "-m", "Create file", "--no-verify", "--", name]);
 
-        let mut file = File::create(self.location.path().join(name)).unwrap();
+        let mut file = File::create(self.path(name)).unwrap();
         file.write_all(lines_after.as_bytes()).unwrap();
 
         self.git(&["add", name]);
     }
 
+    /// Create a file in the working directory without staging it.
+    pub fn write_file(&self, name: &str, contents: &str) {
+        let mut file = File::create(self.path(name)).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+    }
+
+    /// Write a file and commit it with the message.
+    pub fn commit_file(&self, name: &str, contents: &str, message: &str) {
+        self.write_file(name, contents);
+        self.git(&["add", name]);
+        self.git(&["commit", "-m", message, "--no-verify", "--", name]);
+    }
+
+    /// Path of a file in the repository, creating its parent directories.
+    fn path(&self, name: &str) -> PathBuf {
+        let path = self.location.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        path
+    }
+
+    /// Root directory of the working tree.
+    pub fn root(&self) -> &Path {
+        self.location.path()
+    }
+
     pub fn git_repo(&self) -> &Repository {
         &self.git_repo
     }
 
     /// Run a git command
     pub fn git(&self, cmd: &[&str]) {
-        let output = Command::new("git")
-            .args(["-c", "commit.gpgsign=false"])
+        let output = git_command()
             .args(cmd)
             .current_dir(&self.location)
             .output()
@@ -69,6 +105,37 @@ impl TestRepository {
     }
 }
 
+/// Run git with `show-changed-tests` on the path, like after installing it.
+pub fn git_with_binary(dir: &Path, args: &[&str]) {
+    let binary = Path::new(env!("CARGO_BIN_EXE_show-changed-tests"));
+    let mut paths = vec![binary.parent().unwrap().to_owned()];
+    paths.extend(env::split_paths(&env::var_os("PATH").unwrap_or_default()));
+
+    let output = git_command()
+        .args(args)
+        .current_dir(dir)
+        .env("PATH", env::join_paths(paths).unwrap())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// A git command with a fixed identity, independent of the user running the tests.
+fn git_command() -> Command {
+    let mut command = Command::new("git");
+    command
+        .args(["-c", "commit.gpgsign=false"])
+        .env("GIT_AUTHOR_NAME", "t")
+        .env("GIT_AUTHOR_EMAIL", "t@example.com")
+        .env("GIT_COMMITTER_NAME", "t")
+        .env("GIT_COMMITTER_EMAIL", "t@example.com");
+    command
+}
+
 enum DiffKind {
     Old,
     New,
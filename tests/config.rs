@@ -0,0 +1,195 @@
+mod common;
+
+use std::error::Error;
+
+use common::*;
+use show_changed_tests::{
+    config::{Config, Outcome},
+    Policy,
+};
+
+#[test]
+fn missing_configuration_uses_defaults() {
+    let repo = TestRepository::new();
+
+    let config = Config::load(repo.git_repo()).unwrap();
+
+    assert_eq!(config.options().test_prefix, "tc:");
+    assert_eq!(config.trailer(), "Tests");
+    assert_eq!(config.width(), 72);
+    assert_eq!(config.untagged(), Policy::Ignore);
+}
+
+#[test]
+fn configuration_file_is_loaded() {
+    let repo = TestRepository::new();
+    repo.write_file(
+        ".show-changed-tests.toml",
+        r#"
+prefix = "test:"
+trailer = "Issues"
+width = 50
+untagged = "warn"
+"#,
+    );
+
+    let config = Config::load(repo.git_repo()).unwrap();
+
+    assert_eq!(config.options().test_prefix, "test:");
+    assert_eq!(config.trailer(), "Issues");
+    assert_eq!(config.width(), 50);
+    assert_eq!(config.untagged(), Policy::Warn);
+}
+
+#[test]
+fn git_config_overrides_configuration_file() {
+    let repo = TestRepository::new();
+    repo.write_file(
+        ".show-changed-tests.toml",
+        r#"
+prefix = "test:"
+trailer = "Issues"
+"#,
+    );
+    repo.git(&["config", "showChangedTests.prefix", "id:"]);
+    repo.git(&["config", "showchangedtests.untagged", "fail"]);
+
+    let config = Config::load(repo.git_repo()).unwrap();
+
+    assert_eq!(config.options().test_prefix, "id:");
+    assert_eq!(config.trailer(), "Issues");
+    assert_eq!(config.untagged(), Policy::Fail);
+}
+
+#[test]
+fn command_line_overrides_git_config() {
+    let repo = TestRepository::new();
+    repo.git(&["config", "showChangedTests.trailer", "Issues"]);
+    repo.git(&["config", "showChangedTests.width", "60"]);
+    repo.git(&["config", "showChangedTests.strict", "true"]);
+
+    let cli = Config {
+        trailer: Some("Refs".into()),
+        // Like `--no-strict`
+        strict: Some(false),
+        ..Default::default()
+    };
+    let config = Config::load(repo.git_repo()).unwrap().merge(cli);
+
+    assert_eq!(config.trailer(), "Refs");
+    assert_eq!(config.width(), 60);
+    assert_eq!(config.exit_code(Outcome::Error), 0);
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    assert!(Config::from_toml("untagged = \"sometimes\"").is_err());
+    assert!(Config::from_toml("unknown = 1").is_err());
+
+    let repo = TestRepository::new();
+    repo.git(&["config", "showChangedTests.width", "wide"]);
+
+    assert!(Config::load(repo.git_repo()).is_err());
+}
+
+#[test]
+fn file_patterns_can_be_repeated_in_git_config() {
+    let repo = TestRepository::new();
+    repo.write_file(
+        ".show-changed-tests.toml",
+        r#"
+include = ["**/*.feature"]
+exclude = ["vendor/**"]
+"#,
+    );
+    repo.git(&["config", "--add", "showChangedTests.include", "**/*.story"]);
+    repo.git(&[
+        "config",
+        "--add",
+        "showChangedTests.include",
+        "**/*.feature.md",
+    ]);
+
+    let options = Config::load(repo.git_repo()).unwrap().options();
+
+    assert_eq!(options.include, ["**/*.story", "**/*.feature.md"]);
+    assert_eq!(options.exclude, ["vendor/**"]);
+}
+
+#[test]
+fn fixtures_are_read_from_configuration_file() {
+    let repo = TestRepository::new();
+    repo.write_file(
+        ".show-changed-tests.toml",
+        r#"
+fixture-references = true
+
+[fixtures]
+"fixtures/*.json" = [1, 2]
+"#,
+    );
+    repo.git(&["config", "showChangedTests.fixtureReferences", "false"]);
+
+    let options = Config::load(repo.git_repo()).unwrap().options();
+
+    assert!(!options.fixture_references);
+    assert_eq!(options.fixtures["fixtures/*.json"], [1, 2]);
+}
+
+#[test]
+fn invalid_configuration_file_keeps_the_parse_error_as_source() {
+    let repo = TestRepository::new();
+    repo.write_file(".show-changed-tests.toml", "width = \"wide\"\n");
+
+    let err = Config::load(repo.git_repo()).unwrap_err();
+
+    assert_eq!(err.to_string(), "Invalid .show-changed-tests.toml");
+    let source = err.source().unwrap().to_string();
+    assert!(source.contains("expected usize"), "{source}");
+}
+
+#[test]
+fn exit_codes_are_lenient_by_default() {
+    let config = Config::default();
+
+    assert_eq!(config.exit_code(Outcome::Error), 0);
+    assert_eq!(config.exit_code(Outcome::ParseError), 0);
+    assert_eq!(config.exit_code(Outcome::Untagged), 0);
+    assert_eq!(config.exit_code(Outcome::NoChanges), 0);
+
+    let fail_untagged = Config::from_toml("untagged = \"fail\"").unwrap();
+    assert_eq!(fail_untagged.exit_code(Outcome::Untagged), 1);
+}
+
+#[test]
+fn strict_mode_fails_on_problems() {
+    let config = Config::from_toml(
+        r#"
+strict = true
+parse-error-exit-code = 4
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.exit_code(Outcome::Error), 1);
+    assert_eq!(config.exit_code(Outcome::ParseError), 4);
+    assert_eq!(config.exit_code(Outcome::Untagged), 1);
+    assert_eq!(config.exit_code(Outcome::NoChanges), 0);
+}
+
+#[test]
+fn exit_codes_are_read_from_git_config() {
+    let repo = TestRepository::new();
+    repo.git(&["config", "showChangedTests.strict", "true"]);
+    repo.git(&["config", "showChangedTests.untaggedExitCode", "0"]);
+    repo.git(&["config", "showChangedTests.noChangesExitCode", "2"]);
+
+    let config = Config::load(repo.git_repo()).unwrap();
+
+    assert_eq!(config.exit_code(Outcome::ParseError), 1);
+    assert_eq!(config.exit_code(Outcome::Untagged), 0);
+    assert_eq!(config.exit_code(Outcome::NoChanges), 2);
+
+    repo.git(&["config", "showChangedTests.noChangesExitCode", "-1"]);
+    assert!(Config::load(repo.git_repo()).is_err());
+}
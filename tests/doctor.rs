@@ -0,0 +1,146 @@
+mod common;
+
+use common::*;
+use show_changed_tests::{
+    config::Config,
+    doctor::{diagnose, Check, Severity},
+    install::Hook,
+};
+
+const FEATURES: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+
+  Scenario: Declined withdrawal
+    When Hamza tries to withdraw 200.00
+";
+
+fn find<'a>(checks: &'a [Check], topic: &str) -> Vec<&'a Check> {
+    checks.iter().filter(|c| c.topic == topic).collect()
+}
+
+#[test]
+fn missing_hook_is_a_problem() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURES);
+
+    let checks = diagnose(repo.git_repo(), Config::default());
+
+    let hook = find(&checks, "hook");
+    assert_eq!(hook.len(), 1);
+    assert_eq!(hook[0].severity, Severity::Problem);
+    assert!(hook[0].message.contains("show-changed-tests install"));
+}
+
+#[test]
+fn healthy_repository_has_no_problems() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURES);
+    Hook::locate(repo.git_repo()).unwrap().install().unwrap();
+
+    let checks = diagnose(repo.git_repo(), Config::default());
+
+    assert!(
+        checks.iter().all(|c| c.severity == Severity::Ok),
+        "{checks:#?}"
+    );
+    let config = find(&checks, "config");
+    assert!(config[0].message.contains("prefix 'tc:'"));
+    assert!(config[0].message.contains("from defaults"));
+    assert_eq!(
+        find(&checks, "tests")[0].message,
+        "1 of 2 test cases in 1 file have a tag with prefix 'tc:'"
+    );
+}
+
+#[test]
+fn prefix_without_matching_tags_is_a_problem() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURES);
+    repo.git(&["config", "showChangedTests.prefix", "id:"]);
+
+    let checks = diagnose(repo.git_repo(), Config::default());
+
+    assert!(find(&checks, "config")[0]
+        .message
+        .contains("from git config"));
+    let tests = find(&checks, "tests");
+    assert_eq!(tests[0].severity, Severity::Problem);
+    assert_eq!(
+        tests[0].message,
+        "None of the 2 test cases has a tag with prefix 'id:'"
+    );
+}
+
+#[test]
+fn unparsable_features_are_listed() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURES);
+    repo.add_file("broken.feature", "+@dangling\n");
+
+    let checks = diagnose(repo.git_repo(), Config::default());
+
+    let parse = find(&checks, "parse");
+    assert_eq!(parse.len(), 1);
+    assert_eq!(parse[0].severity, Severity::Problem);
+    assert!(parse[0].message.contains("broken.feature"));
+    assert!(find(&checks, "tests")[0].message.contains("in 2 files"));
+}
+
+#[test]
+fn invalid_configuration_is_a_problem() {
+    let repo = TestRepository::new();
+    repo.write_file(".show-changed-tests.toml", "untagged = \"sometimes\"\n");
+
+    let checks = diagnose(repo.git_repo(), Config::default());
+
+    let config = find(&checks, "config");
+    assert_eq!(config[0].severity, Severity::Problem);
+    assert!(find(&checks, "tests").is_empty());
+}
+
+#[test]
+fn hand_written_hook_must_pass_arguments() {
+    let repo = TestRepository::new();
+    repo.write_file(
+        ".git/hooks/prepare-commit-msg",
+        "#!/bin/sh\nshow-changed-tests\n",
+    );
+
+    let checks = diagnose(repo.git_repo(), Config::default());
+
+    let hook = find(&checks, "hook");
+    assert_eq!(hook[0].severity, Severity::Problem);
+    assert!(hook[0].message.contains("-- \"$@\""), "{}", hook[0].message);
+}
+
+#[test]
+fn pre_commit_must_install_the_hook_stage() {
+    let repo = TestRepository::new();
+    repo.write_file(
+        ".pre-commit-config.yaml",
+        "repos:
+-   repo: https://github.com/dev-threads/show-changed-tests
+    rev: v1.0.1
+    hooks:
+    -   id: show-changed-tests
+",
+    );
+
+    let checks = diagnose(repo.git_repo(), Config::default());
+    let pre_commit = find(&checks, "pre-commit");
+    assert_eq!(pre_commit[0].severity, Severity::Problem);
+
+    repo.write_file(
+        ".pre-commit-config.yaml",
+        "default_install_hook_types: [pre-commit, prepare-commit-msg]
+repos:
+-   repo: https://github.com/dev-threads/show-changed-tests
+",
+    );
+    let checks = diagnose(repo.git_repo(), Config::default());
+    assert_eq!(find(&checks, "pre-commit")[0].severity, Severity::Ok);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::error::Error;
+
+use common::*;
+use show_changed_tests::{changed_tests, ExtractNumberError, Operation, Options, Warning};
+
+#[test]
+fn git_errors_carry_operation_and_source() {
+    // Without a commit, HEAD does not point to anything
+    let repo = TestRepository::new();
+
+    let err = changed_tests(repo.git_repo(), &Options::default()).unwrap_err();
+
+    assert!(matches!(
+        err,
+        ExtractNumberError::Git {
+            operation: Operation::ResolveHead,
+            path: None,
+            ..
+        }
+    ));
+    assert!(err.source().is_some());
+    assert!(err.to_string().starts_with("Failed to resolve HEAD: "));
+}
+
+#[test]
+fn invalid_glob_pattern() {
+    let mut repo = TestRepository::new();
+    repo.add_file("a.feature", "Feature: A\n");
+    let opts = Options {
+        include: vec!["[unclosed".into()],
+        ..Default::default()
+    };
+
+    let err = changed_tests(repo.git_repo(), &opts).unwrap_err();
+
+    assert!(matches!(err, ExtractNumberError::InvalidPattern(_)));
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn invalid_step_pattern_is_a_warning() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  @tc:1
+  Scenario: Withdrawal
+    When Alice withdraws 100.00
+",
+    );
+    repo.add_file(
+        "steps.rs",
+        r#"
+#[when(regex = r"^\w+ withdraws{2,1} .*$")]
+async fn withdraw(world: &mut World) {
+    -world.withdraw();
+    +world.withdraw_all();
+}
+"#,
+    );
+    let opts = Options {
+        step_definitions: vec!["steps.rs".into()],
+        ..Default::default()
+    };
+
+    let changes = changed_tests(repo.git_repo(), &opts).unwrap();
+
+    assert!(changes.numbers.is_empty());
+    assert!(matches!(
+        &changes.warnings[..],
+        [Warning::InvalidStepPattern { function, .. }] if function == "withdraw"
+    ));
+}
+
+#[test]
+fn unparsable_features_are_reported_once() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "broken.feature",
+        "
+Feature: Broken
+  Scenario: Fine
+    -Given a step
+    +Given another step
+  +@dangling
+",
+    );
+    repo.add_file("data.json", "-1\n+2");
+    let opts = Options {
+        fixture_references: true,
+        ..Default::default()
+    };
+
+    let changes = changed_tests(repo.git_repo(), &opts).unwrap();
+
+    assert!(matches!(
+        &changes.warnings[..],
+        [Warning::Parse(error)] if error.path.ends_with("broken.feature")
+    ));
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::*;
+use serde_json::{json, Value};
+use show_changed_tests::{
+    export::{changed_in_range, Export},
+    history::NumberCache,
+    report::{self, ExportFormat},
+    Options,
+};
+
+const FEATURE: &str = "Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    # Enough money
+    Given Alice has 300.00 in their account
+    When Alice tries to withdraw 200.00
+
+  @tc:1002 @smoke
+  Scenario: Declined withdrawal
+    When Hamza tries to withdraw 200.00
+";
+
+/// #1001 and #1002 are added, only #1002 changes after the tag `v1`.
+fn exported(range: &str) -> Export {
+    let repo = TestRepository::new();
+    repo.commit_file("features/withdraw.feature", FEATURE, "Add withdrawal");
+    repo.git(&["tag", "v1"]);
+    repo.commit_file(
+        "features/withdraw.feature",
+        &FEATURE.replace("Hamza tries", "Hamza tries again"),
+        "Retry declined withdrawal",
+    );
+
+    changed_in_range(
+        repo.git_repo(),
+        range,
+        &Options::default(),
+        &mut NumberCache::in_memory(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn latest_version_of_changed_tests_is_exported() {
+    let changed = exported("v1..HEAD");
+
+    assert_eq!(changed.tests.len(), 1);
+    let test = &changed.tests[0].test;
+    assert_eq!(test.number, Some(1002));
+    assert_eq!(test.steps, ["When Hamza tries again to withdraw 200.00"]);
+
+    let all = exported("HEAD");
+    let numbers: Vec<_> = all.tests.iter().map(|t| t.test.number).collect();
+    assert_eq!(numbers, [Some(1001), Some(1002)]);
+    assert_eq!(
+        all.tests[0].test.steps,
+        [
+            "Given Alice has 300.00 in their account",
+            "When Alice tries to withdraw 200.00"
+        ]
+    );
+    assert_ne!(all.tests[0].last_commit, all.tests[1].last_commit);
+}
+
+#[test]
+fn csv_contains_the_steps() {
+    let changed = exported("v1..HEAD");
+
+    let csv = report::export(&changed.tests, ExportFormat::Csv, None);
+
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("id,file,line,feature,scenario,steps,last_commit,last_modified")
+    );
+    assert!(lines.next().unwrap().starts_with(
+        "1002,features/withdraw.feature,10,Withdrawing cash,Declined withdrawal,\
+         When Hamza tries again to withdraw 200.00,"
+    ));
+}
+
+#[test]
+fn xray_updates_the_issues() {
+    let changed = exported("HEAD");
+
+    let output: Value = serde_json::from_str(&report::export(
+        &changed.tests,
+        ExportFormat::Xray,
+        Some("BANK-"),
+    ))
+    .unwrap();
+
+    assert_eq!(output[1]["testtype"], "Cucumber");
+    assert_eq!(output[1]["update_key"], "BANK-1002");
+    assert_eq!(output[1]["fields"]["summary"], "Declined withdrawal");
+    assert_eq!(output[1]["fields"]["labels"], json!(["tc:1002", "smoke"]));
+    assert_eq!(
+        output[0]["gherkin_def"],
+        "Given Alice has 300.00 in their account\nWhen Alice tries to withdraw 200.00"
+    );
+    assert_eq!(output[0]["xray_test_repository_folder"], "features");
+
+    let output: Value =
+        serde_json::from_str(&report::export(&changed.tests, ExportFormat::Xray, None)).unwrap();
+    assert!(output[0].get("update_key").is_none());
+}
+
+#[test]
+fn testrail_cases_have_separated_steps() {
+    let changed = exported("v1..HEAD");
+
+    let output: Value = serde_json::from_str(&report::export(
+        &changed.tests,
+        ExportFormat::Testrail,
+        None,
+    ))
+    .unwrap();
+
+    let case = &output["cases"][0];
+    assert_eq!(case["custom_tracking_number"], 1002);
+    // The id is assigned by TestRail
+    assert_eq!(case.get("id"), None);
+    assert_eq!(case["title"], "Declined withdrawal");
+    assert_eq!(case["section"], "Withdrawing cash");
+    assert_eq!(case["refs"], "features/withdraw.feature");
+    assert_eq!(
+        case["custom_steps_separated"],
+        json!([{"content": "When Hamza tries again to withdraw 200.00", "expected": ""}])
+    );
+    assert_eq!(case["custom_last_modified"].as_str().unwrap().len(), 10);
+}
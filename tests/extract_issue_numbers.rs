@@ -1,7 +1,7 @@
 mod common;
 
 use common::*;
-use show_changed_tests::changed_test_numbers;
+use show_changed_tests::{changed_test_numbers, changed_tests};
 
 fn check(files: &[(&'static str, &'static str)], numbers: &[u32]) {
     let mut repo = TestRepository::new();
@@ -286,6 +286,44 @@ fn test_background_change_affects_all_scenarios() {
     );
 }
 
+#[test]
+fn change_in_rule_is_detected() {
+    check(
+        &[(
+            "SimpleChange.feature",
+            "
+        Feature: Scenarios inside rules are detected
+
+        Rule: First rule
+
+          Background:
+            - Given a line in the rule background changed
+            + Given this line in the rule background changed
+
+          @tc:111
+          Scenario: Scenario in changed rule background
+            Given a simple test scenario with number 111
+            Then 111 is in the output
+
+        Rule: Second rule
+
+          @tc:222
+          Scenario: Changed scenario in rule
+            Given a simple test scenario with number 222
+            -When a line is changed
+            +When this line is changed
+            Then 222 is in the output
+
+          @tc:333
+          Scenario: Unchanged scenario in rule
+            Given a simple test scenario with number 333
+            Then 333 is not in the output
+        ",
+        )],
+        &[111, 222],
+    );
+}
+
 #[test]
 fn gracefully_handle_parse_errors() {
     check(
@@ -309,6 +347,73 @@ fn gracefully_handle_parse_errors() {
     );
 }
 
+#[test]
+fn untagged_scenarios_are_reported_separately() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "Untagged.feature",
+        "
+        Feature: Report scenarios without tracking number
+
+        @tc:111
+        Scenario: Tagged scenario is changed
+          Given a simple test scenario with number 111
+          -When a line is changed
+          +When this line is changed
+          Then 111 is in the output
+
+        Scenario: Untagged scenario is changed
+          Given a simple test scenario without number
+          +When a line is added
+          Then it is reported as untagged
+
+        @smoke
+        Scenario: Scenario with unrelated tags is unchanged
+          Given a simple test scenario without number
+          When nothing is changed
+          Then it is not reported
+        ",
+    );
+
+    let changes = changed_tests(repo.git_repo(), &Default::default()).unwrap();
+
+    assert_eq!(changes.numbers, vec![111]);
+    assert_eq!(changes.untagged.len(), 1);
+    assert_eq!(changes.untagged[0].name, "Untagged scenario is changed");
+    assert_eq!(changes.untagged[0].path.to_str(), Some("Untagged.feature"));
+    assert_eq!(changes.untagged[0].line, 10);
+}
+
+#[test]
+fn background_change_reports_untagged_scenarios() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "Untagged.feature",
+        "
+        Feature: Background change affects untagged scenarios
+
+        Background:
+            - Given a line in the background changed
+            + Given this line in the background changed
+
+        @tc:111
+        Scenario: Tagged scenario
+          Given a simple test scenario with number 111
+          Then 111 is in the output
+
+        Scenario: Untagged scenario
+          Given a simple test scenario without number
+          Then it is reported as untagged
+        ",
+    );
+
+    let changes = changed_tests(repo.git_repo(), &Default::default()).unwrap();
+
+    assert_eq!(changes.numbers, vec![111]);
+    let names: Vec<_> = changes.untagged.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Untagged scenario"]);
+}
+
 /// Known issue, the span information does not include the tags at the beginning of the scenario.
 #[test]
 #[should_panic]
@@ -346,7 +451,7 @@ fn repro_span_issue() {
             Then the text referenced by the span is this scenario
         ";
 
-    let feature = gherkin::Feature::parse(&text, Default::default()).unwrap();
+    let feature = gherkin::Feature::parse(text, Default::default()).unwrap();
     let span = feature.scenarios[0].span;
 
     let scenario = "\
@@ -360,3 +465,95 @@ fn repro_span_issue() {
 
     assert_eq!(&text[span.start..span.end], scenario);
 }
+
+#[test]
+fn unstaged_changes_are_ignored() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "Staged.feature",
+        "
+Feature: Only the staged version is checked
+
+  @tc:1
+  Scenario: First
+    Given a step
+
+  @tc:2
+  Scenario: Second
+    -Given a step
+    +Given a changed step
+",
+    );
+    // Unstaged lines in front of the change shift the line numbers of the working directory
+    repo.write_file(
+        "Staged.feature",
+        "
+Feature: Only the staged version is checked
+
+  @tc:1
+  Scenario: First
+    Given a step
+    And more unstaged steps
+    And more unstaged steps
+    And more unstaged steps
+    And more unstaged steps
+
+  @tc:2
+  Scenario: Second
+    Given a changed step
+",
+    );
+
+    let numbers = changed_test_numbers(repo.git_repo(), &Default::default()).unwrap();
+
+    assert_eq!(numbers, [2]);
+}
+
+#[test]
+fn renamed_scenario_after_another_one_is_not_attributed_to_it() {
+    check(
+        &[(
+            "Rename.feature",
+            "
+        Feature: Keyword lines belong to their scenario
+
+        @tc:1
+        Scenario: First scenario
+          Given a step
+        @tc:2
+        Scenario: Second scenario
+          Given a step
+
+        @tc:3
+        Scenario: Third scenario
+          Given a step
+        -Scenario: Fourth scenario
+        +Scenario: Renamed fourth scenario
+          Given a step
+        ",
+        )],
+        &[],
+    );
+}
+
+#[test]
+fn offsets_of_late_lines_do_not_drift() {
+    let steps: String = (0..40).map(|i| format!("    Given step {i}\n")).collect();
+    let text: &'static str = format!(
+        "
+Feature: Long scenarios
+
+  @tc:1
+  Scenario: First
+{steps}    -Then it is done
+    +Then it is finally done
+
+  @tc:2
+  Scenario: Second
+    Given a step
+"
+    )
+    .leak();
+
+    check(&[("Long.feature", text)], &[1]);
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use show_changed_tests::{
+    changed_test_numbers,
+    formats::{Gherkin, TestFormat},
+    Options,
+};
+
+const SCENARIO: &str = "
+        Feature: Detect single change in scenario
+
+        @tc:111
+        Scenario: Line in scenario is changed
+          Given a simple test scenario with number 111
+          -When a line is changed
+          +When this line is changed
+          Then 111 is in the output
+        ";
+
+/// Add the same changed scenario to every file,
+/// using the length of the file name as tracking number.
+fn check(files: &[&str], opts: &Options, numbers: &[u32]) {
+    let mut repo = TestRepository::new();
+
+    for name in files {
+        if let Some((dir, _)) = name.rsplit_once('/') {
+            std::fs::create_dir_all(repo.git_repo().workdir().unwrap().join(dir)).unwrap();
+        }
+        repo.add_file(name, &SCENARIO.replace("111", &name.len().to_string()));
+    }
+
+    assert_eq!(
+        &changed_test_numbers(repo.git_repo(), opts).unwrap(),
+        numbers
+    );
+}
+
+#[test]
+fn only_feature_files_are_checked_by_default() {
+    check(
+        &["features/a.feature", "b.feature.md", "c.story"],
+        &Default::default(),
+        &["features/a.feature".len() as u32],
+    );
+}
+
+#[test]
+fn custom_extensions_are_included() {
+    let opts = Options {
+        include: vec![
+            "**/*.feature".into(),
+            "**/*.feature.md".into(),
+            "**/*.story".into(),
+            "**/*.feature.j2".into(),
+        ],
+        ..Default::default()
+    };
+
+    check(
+        &[
+            "a.feature",
+            "bb.feature.md",
+            "ccc/c.story",
+            "dddd.feature.j2",
+            "e.txt",
+        ],
+        &opts,
+        &[9, 11, 13, 15],
+    );
+}
+
+#[test]
+fn excluded_directories_are_skipped() {
+    let opts = Options {
+        exclude: vec!["vendor/**".into(), "**/generated/**".into()],
+        ..Default::default()
+    };
+
+    check(
+        &[
+            "features/a.feature",
+            "vendor/lib/b.feature",
+            "features/generated/cc.feature",
+        ],
+        &opts,
+        &["features/a.feature".len() as u32],
+    );
+}
+
+#[test]
+fn invalid_pattern_is_an_error() {
+    let repo = TestRepository::new();
+    let opts = Options {
+        include: vec!["**/*.{feature".into()],
+        ..Default::default()
+    };
+
+    assert!(changed_test_numbers(repo.git_repo(), &opts).is_err());
+}
+
+#[test]
+fn gherkin_handles_only_feature_and_story_files() {
+    let gherkin = Gherkin::default();
+
+    for path in ["a.feature", "b/b.feature.md", "c.story", "d.feature.j2"] {
+        assert!(gherkin.handles(Path::new(path)), "{path}");
+    }
+    for path in ["README.md", "features/notes.txt", "feature", "a.features"] {
+        assert!(!gherkin.handles(Path::new(path)), "{path}");
+    }
+}
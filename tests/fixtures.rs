@@ -0,0 +1,133 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use common::*;
+use show_changed_tests::{changed_tests, ChangedTests, Options};
+
+const FEATURE: &str = r#"
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Withdrawal with a JSON payload
+    Given the payload "fixtures/withdraw.json"
+    When the request is sent
+    Then the withdrawal is successful
+
+  @tc:1002
+  Scenario: Withdrawal with a file next to the feature
+    Given the payload 'declined.json'
+    When the request is sent
+    Then the withdrawal is declined
+
+  @tc:1003
+  Scenario: Withdrawal described in a doc string
+    Given the request
+      """
+      fixtures/limits.json
+      """
+    When the request is sent
+    Then the withdrawal is declined
+
+  Scenario: Alice's withdrawal from a table
+    Given the accounts
+      | file                 |
+      | fixtures/alice.json  |
+    When Alice withdraws 100.00
+    Then the withdrawal is successful
+"#;
+
+fn changes(fixture: &str, opts: Options) -> ChangedTests {
+    let mut repo = TestRepository::new();
+    repo.add_file("features/withdraw.feature", FEATURE);
+    repo.add_file(fixture, "-{\"amount\": 100}\n+{\"amount\": 200}");
+
+    changed_tests(repo.git_repo(), &opts).unwrap()
+}
+
+fn references() -> Options {
+    Options {
+        fixture_references: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn fixtures_are_ignored_by_default() {
+    let changes = changes("fixtures/withdraw.json", Options::default());
+
+    assert!(changes.numbers.is_empty());
+    assert!(changes.untagged.is_empty());
+}
+
+#[test]
+fn explicit_mapping_reports_numbers() {
+    let opts = Options {
+        fixtures: BTreeMap::from([
+            ("fixtures/*.json".to_owned(), vec![1001, 1004]),
+            ("other/**".to_owned(), vec![2001]),
+        ]),
+        ..Default::default()
+    };
+
+    let changes = changes("fixtures/withdraw.json", opts);
+
+    assert_eq!(changes.numbers, [1001, 1004]);
+}
+
+#[test]
+fn quoted_path_relative_to_repository() {
+    let changes = changes("fixtures/withdraw.json", references());
+
+    assert_eq!(changes.numbers, [1001]);
+}
+
+#[test]
+fn quoted_path_relative_to_feature() {
+    let changes = changes("features/declined.json", references());
+
+    assert_eq!(changes.numbers, [1002]);
+}
+
+#[test]
+fn path_in_doc_string() {
+    let changes = changes("fixtures/limits.json", references());
+
+    assert_eq!(changes.numbers, [1003]);
+}
+
+#[test]
+fn path_in_table_of_untagged_scenario() {
+    let changes = changes("fixtures/alice.json", references());
+
+    assert!(changes.numbers.is_empty());
+    assert_eq!(changes.untagged.len(), 1);
+    assert_eq!(changes.untagged[0].name, "Alice's withdrawal from a table");
+}
+
+#[test]
+fn path_in_background_affects_all_scenarios() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "bank.feature",
+        r#"
+Feature: Bank
+
+  Background:
+    Given the accounts from "accounts.csv"
+
+  @tc:1
+  Scenario: First
+    Then it works
+
+  @tc:2
+  Scenario: Second
+    Then it works too
+"#,
+    );
+    repo.add_file("accounts.csv", "-alice,100\n+alice,200");
+
+    let changes = changed_tests(repo.git_repo(), &references()).unwrap();
+
+    assert_eq!(changes.numbers, [1, 2]);
+}
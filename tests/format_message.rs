@@ -1,4 +1,4 @@
-use show_changed_tests::{extend_message, format_issue_references};
+use show_changed_tests::{extend_message, format_issue_references, parse_issue_references};
 
 fn check(message: &str, numbers: &[u32], expected: &str) {
     let trailer = format_issue_references(numbers, 72, "Tests: ");
@@ -82,3 +82,49 @@ Tests: #10024
 ",
     );
 }
+
+#[test]
+fn narrow_width_puts_each_reference_on_its_own_line() {
+    assert_eq!(
+        format_issue_references(&[1, 22], 5, "Tests: "),
+        "Tests: #1\nTests: #22"
+    );
+}
+
+#[test]
+fn prefix_as_wide_as_width_keeps_the_reference_on_its_line() {
+    assert_eq!(
+        format_issue_references(&[1001], 7, "Tests: "),
+        "Tests: #1001"
+    );
+    assert_eq!(
+        format_issue_references(&[1001, 1002], 3, "Tests: "),
+        "Tests: #1001\nTests: #1002"
+    );
+    // Used to start with a line without reference
+    assert_eq!(
+        format_issue_references(&[1001], 8, "Tests: "),
+        "Tests: #1001"
+    );
+}
+
+#[test]
+fn references_are_parsed_from_wrapped_trailers() {
+    let numbers: Vec<u32> = (1000..1020).collect();
+    let trailer = format_issue_references(&numbers, 30, "Tests: ");
+    let message = extend_message("Change withdrawal\n", &trailer);
+
+    assert_eq!(parse_issue_references(&message, "Tests"), numbers);
+}
+
+#[test]
+fn only_references_of_the_trailer_are_parsed() {
+    let message = "Fix #12 in the summary
+
+Refs: #34
+tests: #1002, #1001 and #x
+Tests: #1001
+";
+
+    assert_eq!(parse_issue_references(message, "Tests"), [1001, 1002]);
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::fs;
+
+use common::*;
+use serde_json::Value;
+use show_changed_tests::{
+    history::{format_date, history, HistoryEntry, NumberCache, CACHE_FILE},
+    Options,
+};
+
+const V1: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+
+  @tc:1002
+  Scenario: Declined withdrawal
+    When Hamza tries to withdraw 200.00
+";
+
+/// Three commits: both scenarios are added, #1002 changes, #1001 changes.
+fn repository() -> TestRepository {
+    let repo = TestRepository::new();
+    repo.commit_file("withdraw.feature", V1, "Add withdrawal");
+    let v2 = V1.replace("Hamza tries", "Hamza tries again");
+    repo.commit_file("withdraw.feature", &v2, "Retry declined withdrawal");
+    let v3 = v2.replace(
+        "Alice tries to withdraw 200.00",
+        "Alice tries to withdraw 250.00",
+    );
+    repo.commit_file("withdraw.feature", &v3, "Raise the amount");
+    repo
+}
+
+fn summaries(entries: &[HistoryEntry]) -> Vec<(&str, &[u32])> {
+    entries
+        .iter()
+        .map(|e| (e.summary.as_str(), e.numbers.as_slice()))
+        .collect()
+}
+
+#[test]
+fn commits_changing_the_number_are_listed() {
+    let repo = repository();
+    let opts = Options::default();
+
+    let entries = history(
+        repo.git_repo(),
+        "HEAD",
+        &[1002],
+        &opts,
+        &mut NumberCache::in_memory(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        summaries(&entries),
+        [
+            ("Retry declined withdrawal", &[1002][..]),
+            ("Add withdrawal", &[1001, 1002][..]),
+        ]
+    );
+    assert_eq!(entries[0].author, "t");
+}
+
+#[test]
+fn without_numbers_all_changing_commits_are_listed() {
+    let repo = repository();
+    repo.commit_file("README.md", "Not a test\n", "Add readme");
+
+    let entries = history(
+        repo.git_repo(),
+        "HEAD~2..HEAD",
+        &[],
+        &Options::default(),
+        &mut NumberCache::in_memory(),
+    )
+    .unwrap();
+
+    assert_eq!(summaries(&entries), [("Raise the amount", &[1001][..])]);
+}
+
+#[test]
+fn invalid_range_is_an_error() {
+    let repo = repository();
+
+    let result = history(
+        repo.git_repo(),
+        "v9.9..HEAD",
+        &[],
+        &Options::default(),
+        &mut NumberCache::in_memory(),
+    );
+
+    assert!(result.unwrap_err().to_string().contains("v9.9..HEAD"));
+}
+
+#[test]
+fn cached_numbers_are_reused() {
+    let repo = repository();
+    let opts = Options::default();
+
+    let mut cache = NumberCache::load(repo.git_repo(), &opts);
+    history(repo.git_repo(), "HEAD", &[], &opts, &mut cache).unwrap();
+    assert_eq!(cache.misses(), 3);
+    cache.save().unwrap();
+
+    // Pretend the last commit changed another number, to see the cache is used
+    let path = repo.git_repo().path().join(CACHE_FILE);
+    let mut contents: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    let head = repo.git_repo().head().unwrap().target().unwrap();
+    assert_eq!(contents["commits"].as_object().unwrap().len(), 3);
+    contents["commits"][head.to_string()] = serde_json::json!([4711]);
+    fs::write(&path, contents.to_string()).unwrap();
+
+    let mut cache = NumberCache::load(repo.git_repo(), &opts);
+    let entries = history(repo.git_repo(), "HEAD", &[4711], &opts, &mut cache).unwrap();
+    assert_eq!(summaries(&entries), [("Raise the amount", &[4711][..])]);
+    assert_eq!(cache.misses(), 0);
+
+    // Other settings detect other numbers, so the cache is discarded
+    let opts = Options {
+        test_prefix: "id:".into(),
+        ..Options::default()
+    };
+    let mut cache = NumberCache::load(repo.git_repo(), &opts);
+    let entries = history(repo.git_repo(), "HEAD", &[4711], &opts, &mut cache).unwrap();
+    assert_eq!(entries, []);
+    assert_eq!(cache.misses(), 3);
+}
+
+#[test]
+fn dates_are_in_the_time_zone_of_the_author() {
+    assert_eq!(format_date(git2::Time::new(0, 0)), "1970-01-01");
+    assert_eq!(format_date(git2::Time::new(951_782_400, 0)), "2000-02-29");
+    assert_eq!(format_date(git2::Time::new(1_714_608_000, 0)), "2024-05-02");
+    assert_eq!(
+        format_date(git2::Time::new(1_714_608_000, -60)),
+        "2024-05-01"
+    );
+}
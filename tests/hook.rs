@@ -0,0 +1,202 @@
+mod common;
+
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Output},
+};
+
+use common::*;
+use tempfile::TempDir;
+
+const FEATURE: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    -When Alice tries to withdraw 200.00
+    +When Alice tries to withdraw 250.00
+";
+
+const UNTAGGED: &str = "
+Feature: Depositing cash
+
+  Scenario: Deposit
+    -When Alice deposits 200.00
+    +When Alice deposits 250.00
+";
+
+/// Run the hook binary in `dir`, like git does for `prepare-commit-msg`.
+fn run_hook(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_show-changed-tests"))
+        .args(args)
+        .current_dir(dir)
+        // Do not find the repository this crate is developed in
+        .env("GIT_CEILING_DIRECTORIES", dir.parent().unwrap())
+        .env_remove("GIT_DIR")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn trailer_is_added_to_message_file() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file("COMMIT_MSG", "Change withdrawal\n");
+
+    let output = run_hook(repo.root(), &["COMMIT_MSG", "message"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(repo.root().join("COMMIT_MSG")).unwrap(),
+        "Change withdrawal\n\nTests: #1001\n"
+    );
+}
+
+#[test]
+fn outside_of_repository_does_not_block_commit() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("COMMIT_MSG"), "Message\n").unwrap();
+
+    let output = run_hook(dir.path(), &["COMMIT_MSG"]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Failed to open the git repository"),
+        "{stderr}"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("COMMIT_MSG")).unwrap(),
+        "Message\n"
+    );
+}
+
+#[test]
+fn exit_code_on_error_is_configurable() {
+    let dir = TempDir::new().unwrap();
+
+    let output = run_hook(dir.path(), &["--error-exit-code", "3"]);
+
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn missing_message_file_does_not_block_commit() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+
+    let output = run_hook(repo.root(), &["does/not/exist"]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Failed to update the commit message"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn read_only_message_file_does_not_block_commit() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.write_file("COMMIT_MSG", "Change withdrawal\n");
+    let path = repo.root().join("COMMIT_MSG");
+    let mut permissions = fs::metadata(&path).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&path, permissions).unwrap();
+    if fs::OpenOptions::new().write(true).open(&path).is_ok() {
+        eprintln!(
+            "skipped: {} is still writable, e.g. when running as root",
+            path.display()
+        );
+        return;
+    }
+
+    let output = run_hook(repo.root(), &["COMMIT_MSG", "message"]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Failed to update the commit message"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn repository_without_commits_does_not_block_commit() {
+    let repo = TestRepository::new();
+
+    let output = run_hook(repo.root(), &[]);
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Failed to resolve HEAD"), "{stderr}");
+}
+
+#[test]
+fn problems_do_not_block_commit_by_default() {
+    let mut repo = TestRepository::new();
+    repo.add_file("broken.feature", "+@dangling\n");
+    repo.add_file("untagged.feature", UNTAGGED);
+
+    let output = run_hook(repo.root(), &[]);
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn strict_mode_fails_on_parse_errors() {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.add_file("broken.feature", "+@dangling\n");
+    repo.write_file("COMMIT_MSG", "Change withdrawal\n");
+
+    let output = run_hook(
+        repo.root(),
+        &["--strict", "--parse-error-exit-code", "5", "COMMIT_MSG"],
+    );
+
+    assert_eq!(output.status.code(), Some(5));
+    // The numbers that were found are still added
+    assert_eq!(
+        fs::read_to_string(repo.root().join("COMMIT_MSG")).unwrap(),
+        "Change withdrawal\n\nTests: #1001\n"
+    );
+}
+
+#[test]
+fn strict_mode_fails_on_untagged_scenarios() {
+    let mut repo = TestRepository::new();
+    repo.add_file("untagged.feature", UNTAGGED);
+
+    let output = run_hook(repo.root(), &["--strict"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("untagged.feature:4: Deposit"), "{stderr}");
+}
+
+#[test]
+fn configured_strict_mode_can_be_disabled() {
+    let mut repo = TestRepository::new();
+    repo.add_file("untagged.feature", UNTAGGED);
+    repo.write_file(".show-changed-tests.toml", "strict = true\n");
+
+    assert_eq!(run_hook(repo.root(), &[]).status.code(), Some(1));
+    assert!(run_hook(repo.root(), &["--no-strict"]).status.success());
+    // The last flag wins
+    let output = run_hook(repo.root(), &["--no-strict", "--strict"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn exit_code_without_changes_is_configurable() {
+    let mut repo = TestRepository::new();
+    repo.add_file("README.md", "-Old\n+New\n");
+
+    assert!(run_hook(repo.root(), &["--strict"]).status.success());
+
+    let output = run_hook(repo.root(), &["--no-changes-exit-code", "2"]);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use common::*;
+use git2::Repository;
+use show_changed_tests::install::{Hook, HookStatus, InstallError, Removal};
+use tempfile::TempDir;
+
+const FOREIGN_HOOK: &str = "#!/bin/sh\necho 'Prepared by another hook' >> \"$1\"\n";
+
+fn hook_path(repo: &TestRepository) -> PathBuf {
+    repo.root().join(".git/hooks/prepare-commit-msg")
+}
+
+#[test]
+fn hook_is_installed_and_removed() {
+    let repo = TestRepository::new();
+    let hook = Hook::locate(repo.git_repo()).unwrap();
+    assert_eq!(hook.path(), hook_path(&repo));
+    assert_eq!(hook.status().unwrap(), HookStatus::Missing);
+
+    assert_eq!(hook.install().unwrap(), HookStatus::Missing);
+    assert_eq!(
+        hook.status().unwrap(),
+        HookStatus::Installed { chained: false }
+    );
+    let script = fs::read_to_string(hook.path()).unwrap();
+    assert!(script.contains("show-changed-tests -- \"$@\""), "{script}");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(hook.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o111, 0o111);
+    }
+
+    // Installing again only updates the script
+    assert_eq!(
+        hook.install().unwrap(),
+        HookStatus::Installed { chained: false }
+    );
+
+    assert_eq!(
+        hook.uninstall().unwrap(),
+        Removal::Removed { restored: false }
+    );
+    assert!(!hook.path().exists());
+    assert_eq!(hook.uninstall().unwrap(), Removal::NotInstalled);
+}
+
+#[test]
+fn existing_hook_is_chained_and_restored() {
+    let repo = TestRepository::new();
+    repo.write_file(".git/hooks/prepare-commit-msg", FOREIGN_HOOK);
+    let hook = Hook::locate(repo.git_repo()).unwrap();
+    assert_eq!(hook.status().unwrap(), HookStatus::Foreign);
+
+    assert_eq!(hook.install().unwrap(), HookStatus::Foreign);
+    assert_eq!(
+        hook.status().unwrap(),
+        HookStatus::Installed { chained: true }
+    );
+    assert_eq!(
+        fs::read_to_string(hook.chained_path()).unwrap(),
+        FOREIGN_HOOK
+    );
+
+    assert_eq!(
+        hook.uninstall().unwrap(),
+        Removal::Removed { restored: true }
+    );
+    assert_eq!(fs::read_to_string(hook.path()).unwrap(), FOREIGN_HOOK);
+    assert!(!hook.chained_path().exists());
+}
+
+#[test]
+fn foreign_hook_is_not_removed() {
+    let repo = TestRepository::new();
+    repo.write_file(".git/hooks/prepare-commit-msg", FOREIGN_HOOK);
+    let hook = Hook::locate(repo.git_repo()).unwrap();
+
+    assert!(matches!(
+        hook.uninstall(),
+        Err(InstallError::ForeignHook(_))
+    ));
+    assert_eq!(fs::read_to_string(hook.path()).unwrap(), FOREIGN_HOOK);
+}
+
+#[test]
+fn occupied_chained_name_is_not_overwritten() {
+    let repo = TestRepository::new();
+    repo.write_file(".git/hooks/prepare-commit-msg", FOREIGN_HOOK);
+    repo.write_file(".git/hooks/prepare-commit-msg.chained", "#!/bin/sh\n");
+    let hook = Hook::locate(repo.git_repo()).unwrap();
+
+    assert!(matches!(
+        hook.install(),
+        Err(InstallError::ChainedHookExists(_))
+    ));
+    assert_eq!(fs::read_to_string(hook.path()).unwrap(), FOREIGN_HOOK);
+}
+
+#[test]
+fn hooks_path_is_honoured() {
+    let repo = TestRepository::new();
+    repo.git(&["config", "core.hooksPath", ".githooks"]);
+
+    let hook = Hook::locate(repo.git_repo()).unwrap();
+    hook.install().unwrap();
+
+    assert_eq!(
+        hook.path(),
+        repo.root().join(".githooks/prepare-commit-msg")
+    );
+    assert!(hook.path().exists());
+}
+
+#[test]
+fn worktrees_share_the_hook_of_the_main_repository() {
+    let mut repo = TestRepository::new();
+    repo.add_file("README.md", "Readme\n");
+    let location = TempDir::new().unwrap();
+    let worktree = location.path().join("worktree");
+    repo.git(&["worktree", "add", worktree.to_str().unwrap()]);
+
+    let hook = Hook::locate(&Repository::open(&worktree).unwrap()).unwrap();
+
+    let hooks_dir = |path: &Path| path.parent().unwrap().canonicalize().unwrap();
+    assert_eq!(hooks_dir(hook.path()), hooks_dir(&hook_path(&repo)));
+    // Displayed without the detour via the git directory of the worktree
+    assert_eq!(hook.path(), hook_path(&repo));
+}
+
+#[cfg(unix)]
+#[test]
+fn installed_hook_runs_on_commit() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    -When Alice tries to withdraw 200.00
+    +When Alice tries to withdraw 250.00
+",
+    );
+    repo.write_file(".git/hooks/prepare-commit-msg", FOREIGN_HOOK);
+    let hook = Hook::locate(repo.git_repo()).unwrap();
+    let chained = hook.chained_path();
+    hook.install().unwrap();
+    // The chained hook keeps its permissions when it is renamed
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(&chained, fs::Permissions::from_mode(0o755)).unwrap();
+
+    git_with_binary(repo.root(), &["commit", "-m", "Change withdrawal"]);
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%B"])
+        .current_dir(repo.root())
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+        "Change withdrawal\nPrepared by another hook\n\nTests: #1001"
+    );
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use std::path::Path;
+
+use common::*;
+use show_changed_tests::{
+    changed_test_numbers, changed_tests, config::Config, formats, Options, Warning,
+};
+
+fn german() -> Options {
+    Options {
+        formats: formats::default_formats("de"),
+        ..Default::default()
+    }
+}
+
+const GERMAN: &str = "
+# language: de
+Funktionalität: Geld abheben
+
+  Grundlage:
+    Angenommen die Bank ist geöffnet
+
+  @tc:1
+  Szenario: Abheben innerhalb des Guthabens
+    Angenommen Alice hat 234,56 auf dem Konto
+    Wenn Alice 200,00 abhebt
+    Dann ist die Abhebung erfolgreich
+
+  Regel: Überziehen ist nicht erlaubt
+
+    Grundlage:
+      Angenommen das Konto hat keinen Dispo
+
+    @tc:2
+    Szenario: Abheben über das Guthaben
+      Angenommen Hamza hat 198,76 auf dem Konto
+      Wenn Hamza 200,00 abhebt
+      Dann wird die Abhebung abgelehnt
+
+    @tc:3
+    Szenariogrundriss: Verschiedene Beträge
+      Angenommen Alice hat 500,00 auf dem Konto
+      Wenn Alice <betrag> abhebt
+      Dann ist die Abhebung <ergebnis>
+
+      Beispiele:
+        | betrag | ergebnis     |
+        | 100    | erfolgreich  |
+        | 600    | abgelehnt    |
+";
+
+fn german_changes(old: &str, new: &str) -> Vec<u32> {
+    let mut repo = TestRepository::new();
+    let text = GERMAN.replacen(old, &format!("-{old}\n+{new}"), 1);
+    repo.add_file("abheben.feature", &text);
+
+    changed_test_numbers(repo.git_repo(), &Options::default()).unwrap()
+}
+
+#[test]
+fn german_scenario() {
+    let numbers = german_changes("Wenn Alice 200,00 abhebt", "    Wenn Alice 150,00 abhebt");
+
+    assert_eq!(numbers, [1]);
+}
+
+#[test]
+fn german_feature_background() {
+    let numbers = german_changes(
+        "Angenommen die Bank ist geöffnet",
+        "    Angenommen die Bank hat geöffnet",
+    );
+
+    assert_eq!(numbers, [1, 2, 3]);
+}
+
+#[test]
+fn german_rule_background() {
+    let numbers = german_changes(
+        "Angenommen das Konto hat keinen Dispo",
+        "      Angenommen das Konto hat keinen Kreditrahmen",
+    );
+
+    assert_eq!(numbers, [2, 3]);
+}
+
+#[test]
+fn german_outline_examples() {
+    let numbers = german_changes(
+        "| 600    | abgelehnt    |",
+        "        | 700    | abgelehnt    |",
+    );
+
+    assert_eq!(numbers, [3]);
+}
+
+const FRENCH: &str = "
+# language: fr
+Fonctionnalité: Retrait d'argent
+
+  Contexte:
+    Soit la banque est ouverte
+
+  Règle: Le solde doit suffire
+
+    @tc:10
+    Scénario: Retrait dans la limite du solde
+      Soit Alice a 234,56 sur son compte
+      Quand Alice retire 200,00
+      Alors le retrait est accepté
+
+    @tc:11
+    Plan du scénario: Retrait de différents montants
+      Soit Alice a 500,00 sur son compte
+      Quand Alice retire <montant>
+      -Alors le retrait est <résultat>
+      +Alors le retrait est bien <résultat>
+
+      Exemples:
+        | montant | résultat |
+        | 100     | accepté  |
+        | 600     | refusé   |
+";
+
+#[test]
+fn french_outline_in_rule() {
+    let mut repo = TestRepository::new();
+    repo.add_file("retrait.feature", FRENCH);
+
+    let numbers = changed_test_numbers(repo.git_repo(), &Options::default()).unwrap();
+
+    assert_eq!(numbers, [11]);
+}
+
+#[test]
+fn default_language_applies_to_files_without_header() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "abheben.feature",
+        "
+Funktionalität: Geld abheben
+
+  @tc:1
+  Szenario: Abheben
+    -Wenn Alice 200,00 abhebt
+    +Wenn Alice 150,00 abhebt
+",
+    );
+
+    let english = changed_tests(repo.git_repo(), &Options::default()).unwrap();
+    let german = changed_tests(repo.git_repo(), &german()).unwrap();
+
+    assert!(english.numbers.is_empty());
+    assert!(matches!(
+        &english.warnings[0],
+        Warning::Parse(error) if error.path == Path::new("abheben.feature")
+    ));
+    assert_eq!(german.numbers, [1]);
+    assert!(german.warnings.is_empty());
+}
+
+#[test]
+fn header_takes_precedence_over_default_language() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+# language: en
+Feature: Withdrawing cash
+
+  @tc:1
+  Scenario: Withdrawal
+    -When Alice withdraws 200.00
+    +When Alice withdraws 150.00
+",
+    );
+
+    let numbers = changed_test_numbers(repo.git_repo(), &german()).unwrap();
+
+    assert_eq!(numbers, [1]);
+}
+
+#[test]
+fn language_header_with_trailing_whitespace() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "abheben.feature",
+        "#language:de   
+Funktionalität: Geld abheben
+
+  @tc:1
+  Szenario: Abheben
+    -Wenn Alice 200,00 abhebt
+    +Wenn Alice 150,00 abhebt
+",
+    );
+
+    let numbers = changed_test_numbers(repo.git_repo(), &Options::default()).unwrap();
+
+    assert_eq!(numbers, [1]);
+}
+
+#[test]
+fn unsupported_language_is_rejected() {
+    assert!(Config::from_toml("language = \"klingon\"").is_err());
+
+    let config = Config::from_toml("language = \"fr\"").unwrap();
+    assert_eq!(config.options().language(), "fr");
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::*;
+use show_changed_tests::{
+    lint::{lint, Issue},
+    scan::{scan, Revision},
+    Options, TestCase,
+};
+
+fn lint_repo(repo: &TestRepository, revision: Revision) -> Vec<Issue> {
+    let opts = Options::default();
+    let scanned = scan(repo.git_repo(), &revision, &opts).unwrap();
+    lint(&scanned, &opts.test_prefix)
+}
+
+fn test_case(path: &str, line: usize, name: &str) -> TestCase {
+    TestCase {
+        path: path.into(),
+        line,
+        name: name.into(),
+    }
+}
+
+#[test]
+fn valid_numbers_have_no_issues() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  @tc:1001 @smoke @tcp
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+
+  Scenario: Untagged withdrawal
+    When Alice tries to withdraw 200.00
+",
+    );
+
+    assert_eq!(lint_repo(&repo, Revision::Index), []);
+}
+
+#[test]
+fn duplicate_numbers_across_files_are_reported() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  @tc:1002
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+",
+    );
+    repo.add_file(
+        "deposit.feature",
+        "
+Feature: Depositing cash
+
+  @tc:1003
+  Scenario: Deposit
+    When Alice deposits 200.00
+
+  @tc:1002
+  Scenario: Copied deposit
+    When Alice deposits 200.00
+",
+    );
+
+    assert_eq!(
+        lint_repo(&repo, Revision::Index),
+        [Issue::DuplicateNumber {
+            number: 1002,
+            tests: vec![
+                test_case("deposit.feature", 9, "Copied deposit"),
+                test_case("withdraw.feature", 5, "Successful withdrawal"),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn malformed_and_multiple_numbers_are_reported() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  @tc:12a @TC:13
+  Scenario: Malformed
+    When Alice tries to withdraw 200.00
+
+  @tc:1001 @tc:1004
+  Scenario: Two numbers
+    When Alice tries to withdraw 200.00
+",
+    );
+
+    let malformed = test_case("withdraw.feature", 5, "Malformed");
+    let two_numbers = test_case("withdraw.feature", 9, "Two numbers");
+    assert_eq!(
+        lint_repo(&repo, Revision::Index),
+        [
+            Issue::MalformedTag {
+                test: malformed.clone(),
+                tag: "tc:12a".into(),
+            },
+            Issue::MalformedTag {
+                test: malformed,
+                tag: "TC:13".into(),
+            },
+            Issue::MultipleNumbers {
+                test: two_numbers,
+                numbers: vec![1001, 1004],
+            },
+        ]
+    );
+}
+
+#[test]
+fn head_and_index_can_be_checked() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  -@tc:10x
+  +@tc:10
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+",
+    );
+
+    assert_eq!(lint_repo(&repo, Revision::Index), []);
+    assert_eq!(
+        lint_repo(&repo, Revision::Commit("HEAD".into())),
+        [Issue::MalformedTag {
+            test: test_case("withdraw.feature", 5, "Successful withdrawal"),
+            tag: "tc:10x".into(),
+        }]
+    );
+}
+
+#[test]
+fn unparsable_files_are_reported() {
+    let mut repo = TestRepository::new();
+    repo.add_file("broken.feature", "+@dangling\n");
+
+    let issues = lint_repo(&repo, Revision::Index);
+
+    assert!(matches!(issues[..], [Issue::Parse(_)]), "{issues:?}");
+}
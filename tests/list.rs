@@ -0,0 +1,116 @@
+mod common;
+
+use common::*;
+use serde_json::{json, Value};
+use show_changed_tests::{
+    report::{list, ListFormat},
+    scan::{scan, Revision, ScannedTests},
+    Options,
+};
+
+const FEATURE: &str = "
+Feature: Withdrawing cash
+
+  @tc:1002 @smoke
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+
+  Scenario: Untagged withdrawal
+    When Alice tries to withdraw 200.00
+
+  @tc:1001
+  Scenario: Withdrawal, \"quoted\"
+    When Alice tries to withdraw 300.00
+";
+
+fn scanned(revision: Revision) -> ScannedTests {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    scan(repo.git_repo(), &revision, &Options::default()).unwrap()
+}
+
+#[test]
+fn scan_knows_the_feature() {
+    let scanned = scanned(Revision::Index);
+
+    let features: Vec<_> = scanned.tests.iter().map(|t| t.feature.as_deref()).collect();
+    assert_eq!(features, [Some("Withdrawing cash"); 3]);
+}
+
+#[test]
+fn text_lists_one_test_case_per_line() {
+    let scanned = scanned(Revision::Index);
+
+    assert_eq!(
+        list(&scanned.tests, ListFormat::Text),
+        "#1002 withdraw.feature:5: Withdrawing cash: Successful withdrawal @tc:1002 @smoke
+- withdraw.feature:8: Withdrawing cash: Untagged withdrawal
+#1001 withdraw.feature:12: Withdrawing cash: Withdrawal, \"quoted\" @tc:1001
+"
+    );
+}
+
+#[test]
+fn csv_fields_are_quoted() {
+    let scanned = scanned(Revision::Index);
+
+    assert_eq!(
+        list(&scanned.tests, ListFormat::Csv),
+        "id,file,line,feature,scenario,tags
+1002,withdraw.feature,5,Withdrawing cash,Successful withdrawal,tc:1002 smoke
+,withdraw.feature,8,Withdrawing cash,Untagged withdrawal,
+1001,withdraw.feature,12,Withdrawing cash,\"Withdrawal, \"\"quoted\"\"\",tc:1001
+"
+    );
+}
+
+#[test]
+fn json_is_an_array_of_records() {
+    let scanned = scanned(Revision::Index);
+
+    let output: Value = serde_json::from_str(&list(&scanned.tests, ListFormat::Json)).unwrap();
+    assert_eq!(
+        output[0],
+        json!({
+            "id": 1002,
+            "file": "withdraw.feature",
+            "line": 5,
+            "feature": "Withdrawing cash",
+            "scenario": "Successful withdrawal",
+            "tags": ["tc:1002", "smoke"],
+        })
+    );
+    assert_eq!(output[1]["id"], Value::Null);
+}
+
+#[test]
+fn committed_revision_is_listed() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
++
++  @tc:1002
++  Scenario: Staged withdrawal
++    When Alice tries to withdraw 300.00
+",
+    );
+
+    let committed = scan(
+        repo.git_repo(),
+        &Revision::Commit("HEAD".into()),
+        &Options::default(),
+    )
+    .unwrap();
+    let staged = scan(repo.git_repo(), &Revision::Index, &Options::default()).unwrap();
+
+    let numbers =
+        |scanned: &ScannedTests| -> Vec<_> { scanned.tests.iter().map(|t| t.number).collect() };
+    assert_eq!(numbers(&committed), [Some(1001)]);
+    assert_eq!(numbers(&staged), [Some(1001), Some(1002)]);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::*;
+use serde_json::Value;
+use show_changed_tests::{
+    config::Config,
+    history::{commit, commits},
+    notes::{annotate, NoteFormat, DEFAULT_NOTES_REF},
+};
+
+const FEATURE: &str = "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    When Alice tries to withdraw 200.00
+";
+
+fn note(repo: &TestRepository, notes_ref: &str, revision: &str) -> Option<String> {
+    let id = repo.git_repo().revparse_single(revision).unwrap().id();
+    let note = repo.git_repo().find_note(Some(notes_ref), id).ok()?;
+    note.message().map(ToOwned::to_owned)
+}
+
+#[test]
+fn trailer_is_stored_in_a_note() {
+    let repo = TestRepository::new();
+    repo.commit_file("withdraw.feature", FEATURE, "Add withdrawal");
+
+    let head = commit(repo.git_repo(), "HEAD").unwrap();
+    let notes = annotate(repo.git_repo(), &[head], &Config::default(), false).unwrap();
+
+    assert_eq!(notes.len(), 1);
+    assert_eq!(
+        note(&repo, DEFAULT_NOTES_REF, "HEAD").as_deref(),
+        Some("Tests: #1001\n")
+    );
+    // The commit itself is unchanged
+    let message = repo.git_repo().head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(message.message(), Some("Add withdrawal\n"));
+}
+
+#[test]
+fn range_is_annotated_as_json() {
+    let repo = TestRepository::new();
+    repo.commit_file("withdraw.feature", FEATURE, "Add withdrawal");
+    repo.commit_file("README.md", "No tests\n", "Add readme");
+    repo.commit_file(
+        "withdraw.feature",
+        &FEATURE.replace("200.00", "250.00"),
+        "Raise the amount",
+    );
+    let config = Config {
+        notes_ref: Some("refs/notes/audit".into()),
+        notes_format: Some(NoteFormat::Json),
+        ..Config::default()
+    };
+
+    let range = commits(repo.git_repo(), "HEAD").unwrap();
+    let notes = annotate(repo.git_repo(), &range, &config, false).unwrap();
+
+    assert_eq!(notes.len(), 2);
+    assert_eq!(note(&repo, "refs/notes/audit", "HEAD~1"), None);
+    let json: Value =
+        serde_json::from_str(&note(&repo, "refs/notes/audit", "HEAD").unwrap()).unwrap();
+    assert_eq!(json["tests"][0]["id"], 1001);
+    assert_eq!(json["tests"][0]["change"], "test_case");
+    assert_eq!(note(&repo, DEFAULT_NOTES_REF, "HEAD"), None);
+}
+
+#[test]
+fn dry_run_writes_no_notes() {
+    let repo = TestRepository::new();
+    repo.commit_file("withdraw.feature", FEATURE, "Add withdrawal");
+
+    let head = commit(repo.git_repo(), "HEAD").unwrap();
+    let notes = annotate(repo.git_repo(), &[head], &Config::default(), true).unwrap();
+
+    assert_eq!(notes[0].text, "Tests: #1001\n");
+    assert_eq!(note(&repo, DEFAULT_NOTES_REF, "HEAD"), None);
+}
+
+#[test]
+fn notes_are_configured() {
+    let repo = TestRepository::new();
+    repo.git(&["config", "showChangedTests.notesRef", "refs/notes/qa"]);
+    repo.git(&["config", "showChangedTests.notesFormat", "json"]);
+
+    let config = Config::load(repo.git_repo()).unwrap();
+    assert_eq!(config.notes_ref(), "refs/notes/qa");
+    assert_eq!(config.notes_format(), NoteFormat::Json);
+
+    let config = Config::from_toml("notes-format = \"trailer\"").unwrap();
+    assert_eq!(config.notes_ref(), DEFAULT_NOTES_REF);
+    assert_eq!(config.notes_format(), NoteFormat::Trailer);
+}
+
+#[cfg(unix)]
+#[test]
+fn post_commit_hook_adds_the_note() {
+    use show_changed_tests::install::{Hook, HookKind};
+
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    -When Alice tries to withdraw 200.00
+    +When Alice tries to withdraw 250.00
+",
+    );
+    let hook = Hook::locate_kind(repo.git_repo(), HookKind::Notes).unwrap();
+    assert!(hook.path().ends_with("hooks/post-commit"));
+    hook.install().unwrap();
+
+    git_with_binary(repo.root(), &["commit", "-m", "Change withdrawal"]);
+
+    assert_eq!(
+        note(&repo, DEFAULT_NOTES_REF, "HEAD").as_deref(),
+        Some("Tests: #1001\n")
+    );
+}
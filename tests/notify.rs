@@ -0,0 +1,249 @@
+#![cfg(feature = "notify")]
+
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::TcpListener,
+    process::Command,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use common::*;
+use git2::Oid;
+use serde_json::{json, Value};
+use show_changed_tests::{
+    config::Config,
+    notify::{Notification, Notifier, NotifyError},
+};
+
+const FEATURE: &str = "Feature: Withdrawing cash
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    Given Alice has 300.00 in their account
+
+  @tc:1002
+  Scenario: Declined withdrawal
+    When Hamza tries to withdraw 200.00
+";
+
+/// A request received by the [`MockServer`].
+#[derive(Debug)]
+struct Received {
+    path: String,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+/// HTTP server answering each request with the next of the given status codes.
+struct MockServer {
+    url: String,
+    requests: Receiver<Received>,
+}
+
+impl MockServer {
+    fn start(statuses: &[u16]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let statuses = statuses.to_vec();
+        let (sender, requests) = mpsc::channel();
+
+        thread::spawn(move || {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let path = line.split(' ').nth(1).unwrap().to_owned();
+
+                let mut headers = BTreeMap::new();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(": ") else {
+                        break;
+                    };
+                    headers.insert(name.to_ascii_lowercase(), value.to_owned());
+                }
+                let length = headers
+                    .get("content-length")
+                    .map_or(0, |length| length.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Mock\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+                sender
+                    .send(Received {
+                        path,
+                        headers,
+                        body: String::from_utf8(body).unwrap(),
+                    })
+                    .unwrap();
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    fn received(&self) -> Vec<Received> {
+        self.requests.try_iter().collect()
+    }
+}
+
+fn notification() -> Notification {
+    Notification {
+        number: 1002,
+        commit: Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap(),
+        range: "v1..HEAD".to_owned(),
+    }
+}
+
+fn notifier(config: Config) -> Notifier {
+    Notifier::from_config(&config)
+        .unwrap()
+        .with_retry_delay(Duration::from_millis(1))
+}
+
+/// Run the binary in the repository.
+fn notify(repo: &TestRepository, args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_show-changed-tests"))
+        .arg("notify")
+        .args(args)
+        .current_dir(repo.root())
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn payload_and_headers_are_posted_until_the_server_accepts() {
+    std::env::set_var("NOTIFY_TEST_TOKEN", "secret");
+    let server = MockServer::start(&[503, 200]);
+    let notifier = notifier(Config {
+        notify_url: Some(format!("{}/tests/{{id}}", server.url)),
+        notify_payload: Some(r#"{"test": {id}, "commit": {commit}, "range": {range}}"#.into()),
+        notify_headers: Some(BTreeMap::from([(
+            "Authorization".to_owned(),
+            "Bearer ${NOTIFY_TEST_TOKEN}".to_owned(),
+        )])),
+        ..Config::default()
+    });
+
+    let delivery = notifier.send(&notification()).unwrap();
+    assert_eq!(delivery.status, 200);
+    assert_eq!(delivery.attempts, 2);
+
+    let received = server.received();
+    assert_eq!(received.len(), 2);
+    for request in received {
+        assert_eq!(request.path, "/tests/1002");
+        assert_eq!(request.headers["authorization"], "Bearer secret");
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(
+            serde_json::from_str::<Value>(&request.body).unwrap(),
+            json!({
+                "test": 1002,
+                "commit": "0123456789abcdef0123456789abcdef01234567",
+                "range": "v1..HEAD",
+            })
+        );
+    }
+}
+
+#[test]
+fn retries_are_limited() {
+    let server = MockServer::start(&[500, 502]);
+    let notifier = notifier(Config {
+        notify_url: Some(server.url.clone()),
+        notify_retries: Some(1),
+        ..Config::default()
+    });
+
+    match notifier.send(&notification()) {
+        Err(NotifyError::Status {
+            attempts, status, ..
+        }) => assert_eq!((attempts, status), (2, 502)),
+        other => panic!("unexpected result {other:?}"),
+    }
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let server = MockServer::start(&[404]);
+    let notifier = notifier(Config {
+        notify_url: Some(server.url.clone()),
+        ..Config::default()
+    });
+
+    match notifier.send(&notification()) {
+        Err(NotifyError::Status {
+            attempts, status, ..
+        }) => assert_eq!((attempts, status), (1, 404)),
+        other => panic!("unexpected result {other:?}"),
+    }
+    assert_eq!(server.received().len(), 1);
+}
+
+#[test]
+fn invalid_payload_is_rejected() {
+    let notifier = notifier(Config {
+        notify_url: Some("http://127.0.0.1:1".into()),
+        notify_payload: Some(r#"{"test": "{id}""#.into()),
+        ..Config::default()
+    });
+
+    assert!(matches!(
+        notifier.request(&notification()),
+        Err(NotifyError::InvalidPayload { .. })
+    ));
+}
+
+#[test]
+fn command_notifies_each_changed_test() {
+    let repo = TestRepository::new();
+    repo.commit_file("features/withdraw.feature", FEATURE, "Add withdrawal");
+    let server = MockServer::start(&[200, 200]);
+
+    let url = format!("{}/tests/{{id}}", server.url);
+    let (success, stdout) = notify(&repo, &["--notify-url", &url, "--no-cache"]);
+
+    assert!(success);
+    assert_eq!(stdout, "#1001: 200\n#1002: 200\n");
+    let paths: Vec<_> = server.received().into_iter().map(|r| r.path).collect();
+    assert_eq!(paths, ["/tests/1001", "/tests/1002"]);
+}
+
+#[test]
+fn dry_run_sends_nothing() {
+    let repo = TestRepository::new();
+    repo.commit_file("features/withdraw.feature", FEATURE, "Add withdrawal");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let url = format!("http://{}/tests/{{id}}", listener.local_addr().unwrap());
+    let head = repo.git_repo().head().unwrap().target().unwrap();
+
+    let (success, stdout) = notify(&repo, &["--notify-url", &url, "--dry-run"]);
+
+    assert!(success);
+    let address = listener.local_addr().unwrap();
+    assert_eq!(
+        stdout,
+        format!(
+            "POST http://{address}/tests/1001\n{{\"id\": 1001, \"commit\": \"{head}\"}}\n\
+             POST http://{address}/tests/1002\n{{\"id\": 1002, \"commit\": \"{head}\"}}\n"
+        )
+    );
+    assert_eq!(listener.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+}
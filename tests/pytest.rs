@@ -0,0 +1,124 @@
+mod common;
+
+use common::*;
+use show_changed_tests::{changed_tests, Options};
+
+fn check(files: &[(&'static str, &'static str)], numbers: &[u32]) {
+    let mut repo = TestRepository::new();
+
+    for (name, content) in files {
+        repo.add_file(name, content);
+    }
+
+    let opts = Options {
+        include: vec!["**/test_*.py".into()],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        changed_tests(repo.git_repo(), &opts).unwrap().numbers,
+        numbers
+    );
+}
+
+#[test]
+fn change_in_test_function_is_detected() {
+    check(
+        &[(
+            "test_withdraw.py",
+            "
+import pytest
+
+
+@pytest.mark.tc(1001)
+def test_successful_withdrawal(account):
+    account.deposit(234.56)
+    -assert account.withdraw(200)
+    +assert account.withdraw(200.00)
+
+
+@pytest.mark.tc(1002)
+def test_declined_withdrawal(account):
+    account.deposit(198.76)
+    assert not account.withdraw(200)
+",
+        )],
+        &[1001],
+    );
+}
+
+#[test]
+fn marker_change_is_detected() {
+    check(
+        &[(
+            "test_withdraw.py",
+            "
+import pytest
+
+
+@pytest.mark.slow
+-@pytest.mark.tc(1001)
++@pytest.mark.tc(1003)
+def test_successful_withdrawal(account):
+    assert account.withdraw(200)
+",
+        )],
+        &[1001, 1003],
+    );
+}
+
+#[test]
+fn methods_and_multiline_decorators_are_detected() {
+    check(
+        &[(
+            "test_withdraw.py",
+            "
+import pytest
+
+
+class TestWithdraw:
+    @pytest.mark.tc('1001')
+    @pytest.mark.parametrize(
+        'amount',
+        [100, 200],
+    )
+    async def test_withdrawal(self, account, amount):
+        -assert account.withdraw(amount)
+        +assert await account.withdraw(amount)
+
+    @pytest.mark.tc(1002)
+    def test_declined_withdrawal(self, account):
+        assert not account.withdraw(1000)
+
+    def helper(self):
+        -return 1
+        +return 2
+",
+        )],
+        &[1001],
+    );
+}
+
+#[test]
+fn untagged_test_function_is_reported() {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "test_withdraw.py",
+        "
+def test_untagged():
+    -assert True
+    +assert not False
+",
+    );
+
+    let opts = Options {
+        include: vec!["**/*.py".into()],
+        ..Default::default()
+    };
+    let changes = changed_tests(repo.git_repo(), &opts).unwrap();
+
+    assert!(changes.numbers.is_empty());
+    assert_eq!(changes.untagged.len(), 1);
+    assert_eq!(changes.untagged[0].name, "test_untagged");
+    assert_eq!(changes.untagged[0].line, 2);
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::*;
+use serde_json::{json, Value};
+use show_changed_tests::{changed_tests, report, ChangedTests};
+
+fn changes() -> ChangedTests {
+    let mut repo = TestRepository::new();
+    repo.add_file(
+        "withdraw.feature",
+        "
+Feature: Withdrawing cash
+
+  Background:
+    -Given the bank is open
+    +Given the bank is open today
+
+  @tc:1001
+  Scenario: Successful withdrawal
+    -Given Alice has 234.56 in their account
+    -When Alice tries to withdraw 200.00
+    +Given Alice has 300.00 in their account
+    +When Alice tries to withdraw 250.00
+    Then the withdrawal is successful
+
+  Scenario: Declined withdrawal
+    Then the withdrawal is declined
+",
+    );
+    repo.add_file(
+        "broken.feature",
+        "
+Feature: Broken
+  Scenario: Fine
+    Given a step
+  +@dangling
+",
+    );
+
+    changed_tests(repo.git_repo(), &Default::default()).unwrap()
+}
+
+#[test]
+fn json_contains_tests_untagged_and_warnings() {
+    let report: Value = serde_json::from_str(&report::json(&changes())).unwrap();
+
+    assert_eq!(
+        report["tests"][0],
+        json!({
+            "id": null,
+            "file": "withdraw.feature",
+            "line": 13,
+            "scenario": "Declined withdrawal",
+            "change": "shared",
+            "cause": "Background",
+            "lines": [
+                {"file": "withdraw.feature", "version": "old", "start": 5, "end": 5},
+                {"file": "withdraw.feature", "version": "new", "start": 5, "end": 5},
+            ],
+        })
+    );
+    assert_eq!(
+        report["tests"][1],
+        json!({
+            "id": 1001,
+            "file": "withdraw.feature",
+            "line": 8,
+            "scenario": "Successful withdrawal",
+            "change": "test_case",
+            "cause": null,
+            "lines": [
+                {"file": "withdraw.feature", "version": "old", "start": 9, "end": 10},
+                {"file": "withdraw.feature", "version": "new", "start": 9, "end": 10},
+            ],
+        })
+    );
+    assert_eq!(
+        report["untagged"],
+        json!([{"file": "withdraw.feature", "line": 13, "scenario": "Declined withdrawal"}])
+    );
+    assert_eq!(report["warnings"][0]["warning"], "parse_error");
+    assert_eq!(report["warnings"][0]["file"], "broken.feature");
+    assert_eq!(report["warnings"][0]["version"], "new");
+}
+
+#[test]
+fn ndjson_has_one_record_per_line() {
+    let output = report::ndjson(&changes());
+
+    let types: Vec<_> = output
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["type"].clone())
+        .collect();
+
+    assert_eq!(types, ["test", "test", "test", "untagged", "warning"]);
+}
+
+#[test]
+fn empty_changes() {
+    let report: Value = serde_json::from_str(&report::json(&ChangedTests::default())).unwrap();
+
+    assert_eq!(report, json!({"tests": [], "untagged": [], "warnings": []}));
+    assert_eq!(report::ndjson(&ChangedTests::default()), "");
+}
+
+#[test]
+fn explain_prints_tree_of_reasons() {
+    let explanation = report::explain(&changes());
+
+    let (tree, errors) = explanation.split_at(explanation.find("Failed").unwrap());
+
+    assert_eq!(
+        tree,
+        "\
+#1001
+└── withdraw.feature:8: Successful withdrawal
+    ├── Test case changed
+    │   ├── withdraw.feature:9 - Given Alice has 234.56 in their account
+    │   ├── withdraw.feature:9 + Given Alice has 300.00 in their account
+    │   ├── withdraw.feature:10 - When Alice tries to withdraw 200.00
+    │   └── withdraw.feature:10 + When Alice tries to withdraw 250.00
+    └── Background changed
+        ├── withdraw.feature:5 - Given the bank is open
+        └── withdraw.feature:5 + Given the bank is open today
+Without tracking number
+└── withdraw.feature:13: Declined withdrawal
+    └── Background changed
+        ├── withdraw.feature:5 - Given the bank is open
+        └── withdraw.feature:5 + Given the bank is open today
+"
+    );
+    assert!(errors.starts_with("Failed to parse gherkin file broken.feature: "));
+}
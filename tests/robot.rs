@@ -0,0 +1,166 @@
+mod common;
+
+use common::*;
+use show_changed_tests::{changed_tests, ChangedTests, Options};
+
+fn changes(files: &[(&'static str, &'static str)]) -> ChangedTests {
+    let mut repo = TestRepository::new();
+
+    for (name, content) in files {
+        repo.add_file(name, content);
+    }
+
+    let opts = Options {
+        include: vec!["**/*.robot".into()],
+        ..Default::default()
+    };
+
+    changed_tests(repo.git_repo(), &opts).unwrap()
+}
+
+fn check(files: &[(&'static str, &'static str)], numbers: &[u32]) {
+    assert_eq!(changes(files).numbers, numbers);
+}
+
+#[test]
+fn change_in_test_case_is_detected() {
+    check(
+        &[(
+            "withdraw.robot",
+            "
+*** Test Cases ***
+Successful Withdrawal Within Balance
+    [Tags]    tc:1001    smoke
+    Account Has Balance    234.56
+    -Withdraw    200
+    +Withdraw    200.00
+    Withdrawal Is Successful
+
+Declined Withdrawal In Excess Of Balance
+    [Tags]    tc:1002
+    Account Has Balance    198.76
+    Withdraw    200
+    Withdrawal Is Declined
+",
+        )],
+        &[1001],
+    );
+}
+
+#[test]
+fn tags_on_continuation_lines_are_found() {
+    check(
+        &[(
+            "withdraw.robot",
+            "
+*** Test Cases ***
+Successful Withdrawal Within Balance
+    [Documentation]    Withdraw some money
+    [Tags]    smoke
+    ...       tc:1001
+    -Withdraw    200
+    +Withdraw    200.00
+",
+        )],
+        &[1001],
+    );
+}
+
+#[test]
+fn settings_tags_apply_to_all_test_cases() {
+    check(
+        &[(
+            "withdraw.robot",
+            "
+*** Settings ***
+Test Tags       tc:1000
+Default Tags    tc:1003
+
+*** Test Cases ***
+Successful Withdrawal Within Balance
+    -Withdraw    200
+    +Withdraw    200.00
+
+Declined Withdrawal In Excess Of Balance
+    [Tags]    tc:1002
+    -Withdraw    200
+    +Withdraw    200.00
+",
+        )],
+        &[1002, 1003],
+    );
+}
+
+#[test]
+fn test_tags_from_settings_are_used_without_own_number() {
+    check(
+        &[(
+            "withdraw.robot",
+            "
+*** Settings ***
+Test Tags       tc:1000
+
+*** Test Cases ***
+Successful Withdrawal Within Balance
+    [Tags]    smoke
+    -Withdraw    200
+    +Withdraw    200.00
+",
+        )],
+        &[1000],
+    );
+}
+
+#[test]
+fn change_in_test_setup_affects_all_test_cases() {
+    check(
+        &[(
+            "withdraw.robot",
+            "
+*** Settings ***
+Library         Bank
+-Test Setup      Open Account
++Test Setup      Open Account    overdraft=0
+
+*** Test Cases ***
+Successful Withdrawal Within Balance
+    [Tags]    tc:1001
+    Withdraw    200
+
+Declined Withdrawal In Excess Of Balance
+    [Tags]    tc:1002
+    Withdraw    200
+
+*** Keywords ***
+Withdraw
+    [Arguments]    ${amount}
+    Log    ${amount}
+",
+        )],
+        &[1001, 1002],
+    );
+}
+
+#[test]
+fn keywords_and_untagged_test_cases() {
+    let changes = changes(&[(
+        "withdraw.robot",
+        "
+*** Test Cases ***
+Untagged Withdrawal
+    -Withdraw    200
+    +Withdraw    300
+
+*** Keywords ***
+Withdraw
+    [Arguments]    ${amount}
+    -Log    ${amount}
+    +Log To Console    ${amount}
+",
+    )]);
+
+    assert!(changes.numbers.is_empty());
+    assert_eq!(changes.untagged.len(), 1);
+    assert_eq!(changes.untagged[0].name, "Untagged Withdrawal");
+    assert_eq!(changes.untagged[0].line, 3);
+}
@@ -0,0 +1,157 @@
+mod common;
+
+use common::*;
+use show_changed_tests::{
+    changed_tests,
+    formats::{Rust, TestFormat},
+    ChangedTests, Options,
+};
+
+fn changes(files: &[(&'static str, &'static str)]) -> ChangedTests {
+    let mut repo = TestRepository::new();
+
+    for (name, content) in files {
+        repo.add_file(name, content);
+    }
+
+    let opts = Options {
+        include: vec!["tests/*.rs".into(), "*.rs".into()],
+        ..Default::default()
+    };
+
+    changed_tests(repo.git_repo(), &opts).unwrap()
+}
+
+fn check(files: &[(&'static str, &'static str)], numbers: &[u32]) {
+    assert_eq!(changes(files).numbers, numbers);
+}
+
+#[test]
+fn change_in_test_function_is_detected() {
+    check(
+        &[(
+            "withdraw.rs",
+            "
+// tc:1001
+#[test]
+fn successful_withdrawal() {
+    let mut account = Account::with_balance(234.56);
+    -assert!(account.withdraw(200.0));
+    +assert!(account.withdraw(200.00));
+}
+
+// tc:1002
+#[test]
+fn declined_withdrawal() {
+    let mut account = Account::with_balance(198.76);
+    assert!(!account.withdraw(200.0));
+}
+",
+        )],
+        &[1001],
+    );
+}
+
+#[test]
+fn tokio_and_rstest_with_doc_comments() {
+    check(
+        &[(
+            "withdraw.rs",
+            r#"
+mod tests {
+    /// Withdrawal within the balance, tc:1001.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn successful_withdrawal() {
+        let account = Account::with_balance(234.56).await;
+        -assert!(account.withdraw(200.0).await);
+        +assert!(account.withdraw(200.00).await);
+    }
+
+    #[rstest]
+    #[case(100)]
+    #[case(200)]
+    #[doc = "tc:1002"]
+    fn declined_withdrawal(#[case] amount: u32) {
+        let text = "not a closing brace: }";
+        let brace = '}';
+        -assert!(!withdraw(amount));
+        +assert!(!withdraw(amount * 2));
+    }
+
+    // tc:1003
+    fn helper() {
+        -todo!()
+        +unimplemented!()
+    }
+}
+"#,
+        )],
+        &[1001, 1002],
+    );
+}
+
+#[test]
+fn tag_change_is_detected() {
+    check(
+        &[(
+            "withdraw.rs",
+            "
+-// tc:1001
++// tc:1003
+#[test]
+fn successful_withdrawal() {
+    assert!(withdraw(200.0));
+}
+",
+        )],
+        &[1001, 1003],
+    );
+}
+
+#[test]
+fn untagged_test_function_is_reported() {
+    let changes = changes(&[(
+        "withdraw.rs",
+        "
+#[test]
+fn untagged() {
+    -assert!(true);
+    +assert!(!false);
+}
+",
+    )]);
+
+    assert!(changes.numbers.is_empty());
+    assert_eq!(changes.untagged.len(), 1);
+    assert_eq!(changes.untagged[0].name, "untagged");
+    assert_eq!(changes.untagged[0].line, 2);
+}
+
+#[test]
+fn unfinished_escaped_char_does_not_panic() {
+    for text in [
+        "#[test]\nfn truncated() {\n    let c = '\\",
+        "#[test]\nfn non_ascii() {\n    let c = '\\é';\n",
+    ] {
+        assert!(Rust.parse(text).is_err(), "{text}");
+    }
+}
+
+#[test]
+fn line_directly_above_test_does_not_change_it() {
+    check(
+        &[(
+            "withdraw.rs",
+            "
+-const LIMIT: f64 = 200.0;
++const LIMIT: f64 = 250.0;
+// tc:1001
+#[test]
+fn successful_withdrawal() {
+    assert!(Account::with_balance(234.56).withdraw(100.0));
+}
+",
+        )],
+        &[],
+    );
+}
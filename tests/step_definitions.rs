@@ -0,0 +1,206 @@
+mod common;
+
+use common::*;
+use show_changed_tests::{
+    changed_tests, steps::StepPattern, ChangeKind, ChangedTests, Options, Version,
+};
+
+const FEATURE: &str = "
+Feature: Withdrawing cash
+
+  Background:
+    Given the bank is open
+
+  @tc:1001
+  Scenario: Successful withdrawal within balance
+    Given Alice has 234.56 in their account
+    When Alice tries to withdraw 200.00
+    Then the withdrawal is successful
+
+  @tc:1002
+  Scenario: Declined withdrawal in excess of balance
+    Given Hamza has 198.76 in their account
+    When Hamza tries to withdraw 200.00
+    Then the withdrawal is declined
+
+  @tc:1003
+  Scenario Outline: Withdrawal of different amounts
+    Given Alice has 500.00 in their account
+    When Alice tries to withdraw <amount>
+    Then the withdrawal is <result>
+
+    Examples:
+      | amount | result     |
+      | 100    | successful |
+      | 600    | declined   |
+";
+
+fn changes(steps_file: &str, steps: &'static str) -> ChangedTests {
+    let mut repo = TestRepository::new();
+    repo.add_file("withdraw.feature", FEATURE);
+    repo.add_file(steps_file, steps);
+
+    let opts = Options {
+        step_definitions: vec!["**/steps.rs".into(), "**/steps.py".into()],
+        ..Default::default()
+    };
+
+    changed_tests(repo.git_repo(), &opts).unwrap()
+}
+
+#[test]
+fn changed_rust_step_definition_affects_scenarios() {
+    let changes = changes(
+        "steps.rs",
+        r#"
+#[given(expr = "{word} has {float} in their account")]
+async fn has_balance(world: &mut World, name: String, amount: f64) {
+    -world.accounts.insert(name, amount);
+    +world.accounts.insert(name, Money::from(amount));
+}
+
+#[then("the withdrawal is declined")]
+async fn declined(world: &mut World) {
+    assert!(!world.success);
+}
+"#,
+    );
+
+    assert_eq!(changes.numbers, [1001, 1002, 1003]);
+}
+
+#[test]
+fn every_changed_line_of_a_definition_is_listed_once() {
+    let changes = changes(
+        "steps.rs",
+        r#"
+#[then("the withdrawal is declined")]
+async fn declined(world: &mut World) {
+    -assert!(!world.success);
+    -assert_eq!(world.error, None);
+    +assert!(world.declined);
+    +assert!(world.error.is_some());
+}
+"#,
+    );
+
+    assert_eq!(changes.numbers, [1002, 1003]);
+    for test in &changes.tests {
+        assert_eq!(
+            test.kind,
+            ChangeKind::StepDefinition {
+                function: "declined".into()
+            }
+        );
+        let lines: Vec<_> = test.lines.iter().map(|l| (l.line, l.version)).collect();
+        assert_eq!(
+            lines,
+            [
+                (4, Version::Old),
+                (4, Version::New),
+                (5, Version::Old),
+                (5, Version::New)
+            ]
+        );
+    }
+}
+
+#[test]
+fn step_type_and_outline_examples_are_considered() {
+    let changes = changes(
+        "steps.rs",
+        r#"
+#[then(regex = r"^the withdrawal is (declined|rejected)$")]
+async fn declined(world: &mut World) {
+    -assert!(!world.success);
+    +assert!(world.declined);
+}
+
+#[given("the withdrawal is declined")]
+async fn not_used(world: &mut World) {
+    -todo!()
+    +unimplemented!()
+}
+"#,
+    );
+
+    assert_eq!(changes.numbers, [1002, 1003]);
+}
+
+#[test]
+fn changed_background_step_affects_all_scenarios() {
+    let changes = changes(
+        "steps.py",
+        r#"
+from behave import given, then
+
+
+@given("the bank is open")
+def bank_is_open(context):
+    -context.bank.open()
+    +context.bank.open(hours=24)
+
+
+@then("the withdrawal is {result}")
+def withdrawal_result(context, result):
+    assert context.result == result
+"#,
+    );
+
+    assert_eq!(changes.numbers, [1001, 1002, 1003]);
+}
+
+#[test]
+fn pytest_bdd_parsers_are_supported() {
+    let changes = changes(
+        "steps.py",
+        r#"
+from pytest_bdd import parsers, when
+
+
+@when(parsers.re(r"(?P<name>\w+) tries to withdraw 200\.00"))
+def withdraw(account, name):
+    -account.withdraw(200)
+    +account.withdraw(Decimal("200.00"))
+"#,
+    );
+
+    assert_eq!(changes.numbers, [1001, 1002]);
+}
+
+#[test]
+fn unchanged_step_definitions_are_ignored() {
+    let changes = changes(
+        "steps.rs",
+        r#"
+#[given(expr = "{word} has {float} in their account")]
+async fn has_balance(world: &mut World, name: String, amount: f64) {
+    world.accounts.insert(name, amount);
+}
+
+-// Helper
++// Helper function
+fn helper() {}
+"#,
+    );
+
+    assert!(changes.numbers.is_empty());
+}
+
+#[test]
+fn cucumber_expressions_are_translated() {
+    let regex = StepPattern::Expression("I have {int} cucumber(s) in my belly/stomach".into())
+        .to_regex()
+        .unwrap();
+
+    assert!(regex.is_match("I have 1 cucumber in my belly"));
+    assert!(regex.is_match("I have 42 cucumbers in my stomach"));
+    assert!(!regex.is_match("I have many cucumbers in my belly"));
+
+    let regex = StepPattern::Expression("the name is {string}".into())
+        .to_regex()
+        .unwrap();
+
+    assert!(regex.is_match("the name is \"Alice\""));
+    assert!(!regex.is_match("the name is Alice"));
+}